@@ -0,0 +1,90 @@
+use card::Card;
+use state::{Player, RNGSeed, RNGSource};
+use {Action, Choice, Decision, Game, State};
+
+use rand::{Rng, SeedableRng};
+
+/// Something that can play a seat in a game
+///
+/// Agents are asked for an action whenever it is their turn, see `Game::play_out`.
+pub trait Agent {
+    /// Action to take on the turn of `player`
+    ///
+    /// The action should be one of `game.valid_actions()`.
+    fn choose_action(&mut self, game: &Game, player: Player) -> Action;
+    /// Choice to answer a decision of `player` with
+    ///
+    /// Defaults to `simple_resolution`.
+    fn choose_resolution(&mut self, game: &Game, player: Player, decision: Decision) -> Choice {
+        let _ = player;
+        simple_resolution(game, decision)
+    }
+}
+
+/// A valid answer to `decision` that involves no strategy
+///
+/// Optional choices are declined, other than always revealing a Moat, the most expensive card is
+/// gained and the first cards in hand are discarded or trashed when forced to.
+pub fn simple_resolution(game: &Game, decision: Decision) -> Choice {
+    let state = game.board_state();
+    let hand: Vec<Card> = state.get_player(decision.player())
+        .map_or(Vec::new(), |p| p.known_hand().into_iter().collect());
+    match decision {
+        Decision::CellarDiscard { .. } | Decision::TrashUpTo { .. } => Choice::Cards(Vec::new()),
+        Decision::MineUpgrade { .. } => Choice::None,
+        Decision::Gain { max_cost, .. } => state.supply_cards_costing(max_cost, false)
+            .into_iter()
+            .max_by_key(|card| card.cost())
+            .map_or(Choice::None, Choice::Card),
+        Decision::MilitiaDiscard { to, .. } => {
+            let discard = hand.len().saturating_sub(to as usize);
+            Choice::Cards(hand.into_iter().take(discard).collect())
+        },
+        Decision::ReactToAttack { .. } => Choice::Bool(true),
+        Decision::RemodelTrash { .. } => hand.into_iter().next().map_or(Choice::None, Choice::Card),
+    }
+}
+
+/// Agent that plays no actions and buys the best money or victory it can afford
+///
+/// Buys a Province with 8 coins, a Gold with 6 or 7 and a Silver with 3 to 5.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BigMoney;
+
+impl Agent for BigMoney {
+    fn choose_action(&mut self, game: &Game, _player: Player) -> Action {
+        if game.state() != State::BuyPhase {
+            return Action::EndAction;
+        }
+        let valid = game.valid_actions();
+        [Card::Province, Card::Gold, Card::Silver].iter()
+            .map(|card| Action::Buy(*card))
+            .find(|buy| valid.contains(buy))
+            .unwrap_or(Action::EndBuy)
+    }
+}
+
+/// Agent that picks uniformly between every valid action
+///
+/// Decisions are answered with `simple_resolution`. The choices only depend on the seed, so a
+/// game between random agents can be reproduced.
+#[derive(Debug, Clone)]
+pub struct RandomAgent {
+    rng: RNGSource,
+}
+
+impl RandomAgent {
+    pub fn new(seed: RNGSeed) -> RandomAgent {
+        RandomAgent { rng: RNGSource::from_seed(seed) }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn choose_action(&mut self, game: &Game, _player: Player) -> Action {
+        let valid = game.valid_actions();
+        if valid.is_empty() {
+            return Action::Concede;
+        }
+        valid[self.rng.gen_range(0, valid.len())]
+    }
+}
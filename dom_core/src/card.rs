@@ -1,9 +1,16 @@
 use rules::Players;
 use enum_map;
 
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::str::FromStr;
+use std::ops::{AddAssign, SubAssign};
+
 /// Enumeration of all different cards
 #[repr(u32)]
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Enum)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Enum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Card {
     // Teasure
     Copper,
@@ -17,6 +24,9 @@ pub enum Card {
     Curse,
     // Action
     Cellar,
+    Chapel,
+    Festival,
+    Laboratory,
     Market,
     Militia,
     Mine,
@@ -28,6 +38,15 @@ pub enum Card {
     Workshop,
 }
 
+/// Fixed resources granted for playing a card
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bonuses {
+    pub cards: u32,
+    pub actions: u32,
+    pub buys: u32,
+    pub coins: u32,
+}
+
 impl Card {
     fn player_victories(players: Players) -> u32 {
         if players == Players::Two {
@@ -42,11 +61,184 @@ impl Card {
             Card::Silver => 40,
             Card::Gold => 30,
             Card::Estate => players as u32 * 3 + Self::player_victories(players),
-            Card::Duchy | Card::Province => Self::player_victories(players),
+            Card::Duchy => Self::player_victories(players),
+            // Larger games get an extra 3 Provinces per player beyond four
+            Card::Province => match players {
+                Players::Five => 15,
+                Players::Six => 18,
+                _ => Self::player_victories(players),
+            },
             Card::Curse => (players as u32 - 1) * 10,
             _ => 10,
         }
     }
+    /// Cost of the card in coins
+    pub fn cost(&self) -> u32 {
+        match *self {
+            Card::Copper | Card::Curse => 0,
+            Card::Estate | Card::Cellar | Card::Chapel | Card::Moat => 2,
+            Card::Silver | Card::Village | Card::Woodcutter | Card::Workshop => 3,
+            Card::Militia | Card::Remodel | Card::Smithy => 4,
+            Card::Duchy | Card::Festival | Card::Laboratory | Card::Market | Card::Mine => 5,
+            Card::Gold => 6,
+            Card::Province => 8,
+        }
+    }
+    /// Coins given by playing the card as a treasure
+    pub fn treasure_value(&self) -> u32 {
+        match *self {
+            Card::Copper => 1,
+            Card::Silver => 2,
+            Card::Gold => 3,
+            _ => 0,
+        }
+    }
+    /// Victory points the card is worth at the end of the game
+    ///
+    /// Signed as Curses are worth negative points.
+    pub fn victory_points(&self) -> i32 {
+        match *self {
+            Card::Estate => 1,
+            Card::Duchy => 3,
+            Card::Province => 6,
+            Card::Curse => -1,
+            _ => 0,
+        }
+    }
+    pub fn is_treasure(&self) -> bool {
+        matches!(*self, Card::Copper | Card::Silver | Card::Gold)
+    }
+    pub fn is_victory(&self) -> bool {
+        matches!(*self, Card::Estate | Card::Duchy | Card::Province)
+    }
+    pub fn is_curse(&self) -> bool {
+        *self == Card::Curse
+    }
+    pub fn is_action(&self) -> bool {
+        matches!(*self,
+            Card::Cellar | Card::Chapel | Card::Festival | Card::Laboratory | Card::Market | Card::Militia | Card::Mine | Card::Moat | Card::Remodel
+                | Card::Smithy | Card::Village | Card::Woodcutter | Card::Workshop)
+    }
+    pub fn is_attack(&self) -> bool {
+        *self == Card::Militia
+    }
+    /// Whether playing the card can trash cards
+    pub fn trashes(&self) -> bool {
+        matches!(*self, Card::Chapel | Card::Mine | Card::Remodel)
+    }
+    /// Resources given for playing the card
+    ///
+    /// This only describes the unconditional '+' effects printed on a card. Anything requiring a
+    /// choice or affecting other players is not included.
+    pub fn bonuses(&self) -> Bonuses {
+        match *self {
+            Card::Cellar => Bonuses { actions: 1, ..Bonuses::default() },
+            Card::Festival => Bonuses { actions: 2, buys: 1, coins: 2, ..Bonuses::default() },
+            Card::Laboratory => Bonuses { cards: 2, actions: 1, ..Bonuses::default() },
+            Card::Market => Bonuses { cards: 1, actions: 1, buys: 1, coins: 1 },
+            Card::Militia => Bonuses { coins: 2, ..Bonuses::default() },
+            Card::Moat => Bonuses { cards: 2, ..Bonuses::default() },
+            Card::Smithy => Bonuses { cards: 3, ..Bonuses::default() },
+            Card::Village => Bonuses { cards: 1, actions: 2, ..Bonuses::default() },
+            Card::Woodcutter => Bonuses { buys: 1, coins: 2, ..Bonuses::default() },
+            _ => Bonuses::default(),
+        }
+    }
+    /// English name of the card
+    pub fn english_name(&self) -> &'static str {
+        match *self {
+            Card::Copper => "Copper",
+            Card::Silver => "Silver",
+            Card::Gold => "Gold",
+            Card::Estate => "Estate",
+            Card::Duchy => "Duchy",
+            Card::Province => "Province",
+            Card::Curse => "Curse",
+            Card::Cellar => "Cellar",
+            Card::Chapel => "Chapel",
+            Card::Festival => "Festival",
+            Card::Laboratory => "Laboratory",
+            Card::Market => "Market",
+            Card::Militia => "Militia",
+            Card::Mine => "Mine",
+            Card::Moat => "Moat",
+            Card::Remodel => "Remodel",
+            Card::Smithy => "Smithy",
+            Card::Village => "Village",
+            Card::Woodcutter => "Woodcutter",
+            Card::Workshop => "Workshop",
+        }
+    }
+    /// Name of the card as given by `names`, falling back to the English name
+    pub fn name<'a>(&self, names: &'a dyn CardNames) -> &'a str {
+        names.name(*self).unwrap_or_else(|| self.english_name())
+    }
+    /// Wrap the card so that it displays using `names`
+    pub fn display_with<'a>(&self, names: &'a dyn CardNames) -> NamedCard<'a> {
+        NamedCard { card: *self, names }
+    }
+}
+
+/// Displays the English name of the card
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.english_name())
+    }
+}
+
+/// Failure to parse a card name
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseCardError {
+    /// The name that did not match any card
+    pub name: String,
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "No card is named {:?}", self.name)
+    }
+}
+
+/// Parses the English name of a card, ignoring case
+impl FromStr for Card {
+    type Err = ParseCardError;
+    fn from_str(s: &str) -> Result<Card, ParseCardError> {
+        let name = s.trim();
+        enum_map::EnumMap::<Card, ()>::new().iter()
+            .map(|(card, _)| card)
+            .find(|card| card.english_name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| ParseCardError { name: s.to_string() })
+    }
+}
+
+/// Source of human readable card names
+///
+/// Front ends can implement this to show cards in another language. Any card without a name
+/// falls back to its English name.
+pub trait CardNames {
+    fn name(&self, card: Card) -> Option<&str>;
+}
+
+/// The default English card names
+#[derive(Debug, Clone, Copy)]
+pub struct English;
+
+impl CardNames for English {
+    fn name(&self, card: Card) -> Option<&str> {
+        Some(card.english_name())
+    }
+}
+
+/// A card that is displayed with a particular set of `CardNames`
+pub struct NamedCard<'a> {
+    card: Card,
+    names: &'a dyn CardNames,
+}
+
+impl<'a> fmt::Display for NamedCard<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.card.name(self.names))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -96,6 +288,13 @@ impl CardSet {
     pub fn contains(&self, card: Card) -> bool {
         self.count(card) > 0
     }
+    /// Number of cards in the set, counting every copy
+    pub fn total_count(&self) -> u32 {
+        self.map.values().sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.total_count() == 0
+    }
     pub fn drain(&mut self) -> CardSetIterator {
         let it = self.into_iter();
         *self = Self::empty();
@@ -104,6 +303,58 @@ impl CardSet {
     pub fn count_iter(&self) -> enum_map::Iter<Card, u32> {
         self.map.iter()
     }
+    /// Set combining the counts of each card with `f`
+    fn combine<F: Fn(u32, u32) -> u32>(&self, other: &CardSet, f: F) -> CardSet {
+        let mut set = CardSet::empty();
+        for (card, count) in self.map.iter() {
+            set.map[card] = f(*count, other.map[card]);
+        }
+        set
+    }
+    /// Set with the larger count of each card
+    pub fn union(&self, other: &CardSet) -> CardSet {
+        self.combine(other, u32::max)
+    }
+    /// Set with the smaller count of each card
+    pub fn intersection(&self, other: &CardSet) -> CardSet {
+        self.combine(other, u32::min)
+    }
+    /// Set with the counts of `other` removed, stopping at zero
+    pub fn difference(&self, other: &CardSet) -> CardSet {
+        self.combine(other, u32::saturating_sub)
+    }
+}
+
+/// Set with one copy per card yielded, so duplicates accumulate
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> CardSet {
+        iter.into_iter().map(|card| (card, 1)).collect()
+    }
+}
+
+/// Set from card and count pairs, with the counts of repeated cards summed
+impl FromIterator<(Card, u32)> for CardSet {
+    fn from_iter<I: IntoIterator<Item = (Card, u32)>>(iter: I) -> CardSet {
+        let mut set = CardSet::empty();
+        for (card, count) in iter {
+            set.insert(card, count);
+        }
+        set
+    }
+}
+
+/// Adds every card of the other set
+impl AddAssign<&CardSet> for CardSet {
+    fn add_assign(&mut self, other: &CardSet) {
+        *self = self.combine(other, |a, b| a + b);
+    }
+}
+
+/// Removes every card of the other set, like `difference`
+impl SubAssign<&CardSet> for CardSet {
+    fn sub_assign(&mut self, other: &CardSet) {
+        *self = self.difference(other);
+    }
 }
 
 impl IntoIterator for CardSet {
@@ -121,16 +372,214 @@ impl IntoIterator for CardSet {
     }
 }
 
+/// Serialized as a map from each card in the set to its count
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for CardSet {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let counts: Vec<(Card, u32)> = self.count_iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(card, count)| (card, *count))
+            .collect();
+        let mut map = serializer.serialize_map(Some(counts.len()))?;
+        for (card, count) in counts {
+            map.serialize_entry(&card, &count)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for CardSet {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<CardSet, D::Error> {
+        struct CardSetVisitor;
+        impl<'de> ::serde::de::Visitor<'de> for CardSetVisitor {
+            type Value = CardSet;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map from cards to counts")
+            }
+            fn visit_map<A: ::serde::de::MapAccess<'de>>(self, mut access: A) -> Result<CardSet, A::Error> {
+                let mut set = CardSet::empty();
+                while let Some((card, count)) = access.next_entry::<Card, u32>()? {
+                    set.insert(card, count);
+                }
+                Ok(set)
+            }
+        }
+        deserializer.deserialize_map(CardSetVisitor)
+    }
+}
+
 impl PartialEq for CardSet {
     fn eq(&self, other: &CardSet) -> bool {
         self.into_iter().eq(other.into_iter())
     }
 }
 
+impl Eq for CardSet {}
+
+/// Hashes the count of every card, so sets built in any order hash the same
+impl Hash for CardSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for count in self.map.values() {
+            count.hash(state);
+        }
+    }
+}
+
 pub mod lists {
     use super::Card;
     pub const FIRST_SET: [Card; 10] = [Card::Cellar, Card::Market, Card::Militia, Card::Mine, Card::Moat, Card::Remodel, Card::Smithy, Card::Village, Card::Woodcutter, Card::Workshop];
 
     pub const BASE_TREASURE: [Card; 3] = [Card::Copper, Card::Silver, Card::Gold];
     pub const BASE_VICTORY: [Card; 3] = [Card::Estate, Card::Duchy, Card::Province];
+
+    /// Every card that can be chosen for the kingdom
+    pub const KINGDOM: &[Card] = &[Card::Cellar, Card::Chapel, Card::Festival, Card::Laboratory, Card::Market, Card::Militia,
+        Card::Mine, Card::Moat, Card::Remodel, Card::Smithy, Card::Village, Card::Woodcutter, Card::Workshop];
+
+    /// Cards each player starts with, dealt from the supply
+    pub const STARTING_DECK: [(Card, u32); 2] = [(Card::Estate, 3), (Card::Copper, 7)];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn curse_counts() {
+        assert_eq!(Card::Curse.starting_count(Players::Two), 10);
+        assert_eq!(Card::Curse.starting_count(Players::Three), 20);
+        assert_eq!(Card::Curse.starting_count(Players::Four), 30);
+        assert_eq!(Card::Curse.starting_count(Players::Six), 50);
+    }
+    #[test]
+    fn victory_counts() {
+        for &(players, victories) in [(Players::Two, 8), (Players::Three, 12), (Players::Four, 12)].iter() {
+            assert_eq!(Card::Duchy.starting_count(players), victories);
+            assert_eq!(Card::Province.starting_count(players), victories);
+            // Each player's starting deck takes 3 Estates from the pile
+            assert_eq!(Card::Estate.starting_count(players), players as u32 * 3 + victories);
+        }
+        for &(players, provinces) in [(Players::Five, 15), (Players::Six, 18)].iter() {
+            assert_eq!(Card::Duchy.starting_count(players), 12);
+            assert_eq!(Card::Province.starting_count(players), provinces);
+            assert_eq!(Card::Estate.starting_count(players), players as u32 * 3 + 12);
+        }
+    }
+    fn card_set(cards: &[(Card, u32)]) -> CardSet {
+        cards.iter().cloned().collect()
+    }
+    #[test]
+    fn card_set_from_iterators() {
+        let cards: CardSet = vec![Card::Copper, Card::Estate, Card::Copper].into_iter().collect();
+        assert_eq!(cards.count(Card::Copper), 2);
+        assert_eq!(cards.count(Card::Estate), 1);
+        assert_eq!(cards.count(Card::Gold), 0);
+        let counts: CardSet = vec![(Card::Gold, 2), (Card::Silver, 0), (Card::Gold, 3)].into_iter().collect();
+        assert_eq!(counts.count(Card::Gold), 5);
+        assert_eq!(counts.count(Card::Silver), 0);
+        assert_eq!(counts.total_count(), 5);
+        assert_eq!(Vec::<Card>::new().into_iter().collect::<CardSet>(), CardSet::empty());
+    }
+    #[test]
+    fn card_set_algebra() {
+        let coppers = card_set(&[(Card::Copper, 3)]);
+        let estates = card_set(&[(Card::Estate, 2)]);
+        let mixed = card_set(&[(Card::Copper, 1), (Card::Estate, 4)]);
+        // Disjoint
+        assert_eq!(coppers.union(&estates), card_set(&[(Card::Copper, 3), (Card::Estate, 2)]));
+        assert_eq!(coppers.intersection(&estates), CardSet::empty());
+        assert_eq!(coppers.difference(&estates), coppers);
+        // Overlapping
+        assert_eq!(coppers.union(&mixed), card_set(&[(Card::Copper, 3), (Card::Estate, 4)]));
+        assert_eq!(coppers.intersection(&mixed), card_set(&[(Card::Copper, 1)]));
+        assert_eq!(coppers.difference(&mixed), card_set(&[(Card::Copper, 2)]));
+        // Subtracting more than there is clamps at zero
+        assert_eq!(estates.difference(&mixed), CardSet::empty());
+        let mut set = coppers;
+        set += &mixed;
+        assert_eq!(set, card_set(&[(Card::Copper, 4), (Card::Estate, 4)]));
+        set -= &estates;
+        set -= &estates;
+        set -= &estates;
+        assert_eq!(set, card_set(&[(Card::Copper, 4)]));
+    }
+    #[test]
+    fn card_set_totals() {
+        assert_eq!(CardSet::empty().total_count(), 0);
+        assert!(CardSet::empty().is_empty());
+        let single = card_set(&[(Card::Gold, 1)]);
+        assert_eq!(single.total_count(), 1);
+        assert!(!single.is_empty());
+        let multi = card_set(&[(Card::Copper, 7), (Card::Estate, 3), (Card::Gold, 1)]);
+        assert_eq!(multi.total_count(), 11);
+        assert!(!multi.is_empty());
+        assert!(multi.difference(&multi).is_empty());
+    }
+    #[test]
+    fn card_names_round_trip() {
+        for (card, _) in enum_map::EnumMap::<Card, ()>::new().iter() {
+            assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+            assert_eq!(card.to_string().to_lowercase().parse::<Card>(), Ok(card));
+        }
+        assert_eq!(" WORKSHOP ".parse::<Card>(), Ok(Card::Workshop));
+        assert_eq!("Platinum".parse::<Card>(), Err(ParseCardError { name: "Platinum".to_string() }));
+    }
+    struct German;
+    impl CardNames for German {
+        fn name(&self, card: Card) -> Option<&str> {
+            match card {
+                Card::Copper => Some("Kupfer"),
+                _ => None,
+            }
+        }
+    }
+    #[test]
+    fn base_set_costs() {
+        // No wildcard, so a new card cannot be added without listing its cost here
+        let costs: enum_map::EnumMap<Card, u32> = enum_map! {
+            Card::Copper => 0, Card::Silver => 3, Card::Gold => 6,
+            Card::Estate => 2, Card::Duchy => 5, Card::Province => 8, Card::Curse => 0,
+            Card::Cellar => 2, Card::Chapel => 2, Card::Festival => 5, Card::Laboratory => 5, Card::Market => 5, Card::Militia => 4, Card::Mine => 5,
+            Card::Moat => 2, Card::Remodel => 4, Card::Smithy => 4, Card::Village => 3,
+            Card::Woodcutter => 3, Card::Workshop => 3,
+        };
+        for (card, cost) in costs.iter() {
+            assert_eq!(card.cost(), *cost, "{:?}", card);
+        }
+    }
+    #[test]
+    fn treasure_and_victory_values() {
+        let values: enum_map::EnumMap<Card, (u32, i32)> = enum_map! {
+            Card::Copper => (1, 0), Card::Silver => (2, 0), Card::Gold => (3, 0),
+            Card::Estate => (0, 1), Card::Duchy => (0, 3), Card::Province => (0, 6), Card::Curse => (0, -1),
+            Card::Cellar => (0, 0), Card::Chapel => (0, 0), Card::Festival => (0, 0), Card::Laboratory => (0, 0), Card::Market => (0, 0), Card::Militia => (0, 0), Card::Mine => (0, 0),
+            Card::Moat => (0, 0), Card::Remodel => (0, 0), Card::Smithy => (0, 0), Card::Village => (0, 0),
+            Card::Woodcutter => (0, 0), Card::Workshop => (0, 0),
+        };
+        for (card, &(treasure, victory)) in values.iter() {
+            assert_eq!(card.treasure_value(), treasure, "{:?}", card);
+            assert_eq!(card.victory_points(), victory, "{:?}", card);
+        }
+    }
+    #[test]
+    fn card_types() {
+        // (treasure, victory, curse, action)
+        let types: enum_map::EnumMap<Card, (bool, bool, bool, bool)> = enum_map! {
+            Card::Copper | Card::Silver | Card::Gold => (true, false, false, false),
+            Card::Estate | Card::Duchy | Card::Province => (false, true, false, false),
+            Card::Curse => (false, false, true, false),
+            Card::Cellar | Card::Chapel | Card::Festival | Card::Laboratory | Card::Market | Card::Militia
+                | Card::Mine | Card::Moat | Card::Remodel | Card::Smithy | Card::Village | Card::Woodcutter | Card::Workshop => (false, false, false, true),
+        };
+        for (card, types) in types.iter() {
+            assert_eq!((card.is_treasure(), card.is_victory(), card.is_curse(), card.is_action()), *types, "{:?}", card);
+        }
+    }
+    #[test]
+    fn localized_names() {
+        assert_eq!(format!("{}", Card::Copper.display_with(&German)), "Kupfer");
+        assert_eq!(format!("{}", Card::Silver.display_with(&German)), "Silver");
+        assert_eq!(format!("{}", Card::Copper.display_with(&English)), "Copper");
+    }
 }
@@ -0,0 +1,62 @@
+use card::Card;
+
+/// Summary of the kinds of effects available in a kingdom
+///
+/// This is a report to help judge how a set will play and has no effect on the game.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct KingdomBalance {
+    pub plus_buy: bool,
+    pub plus_action: bool,
+    pub trashing: bool,
+    pub attacks: bool,
+}
+
+/// Whether a kingdom has terminal draw but nothing to play it with
+///
+/// Terminal draw is an action card that draws cards without giving back an action. Such a
+/// kingdom without any card giving more than one action is considered terminal heavy.
+pub fn kingdom_needs_village(set: &[Card; 10]) -> bool {
+    let terminal_draw = set.iter()
+        .any(|card| card.is_action() && card.bonuses().cards > 0 && card.bonuses().actions == 0);
+    let village = set.iter().any(|card| card.bonuses().actions > 1);
+    terminal_draw && !village
+}
+
+/// Which of +buy, +action, trashing and attacks at least one card in a kingdom offers
+pub fn kingdom_balance(set: &[Card; 10]) -> KingdomBalance {
+    set.iter().fold(KingdomBalance::default(), |balance, card| {
+        KingdomBalance {
+            plus_buy: balance.plus_buy || card.bonuses().buys > 0,
+            plus_action: balance.plus_action || card.bonuses().actions > 0,
+            trashing: balance.trashing || card.trashes(),
+            attacks: balance.attacks || card.is_attack(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::lists;
+    #[test]
+    fn smithy_without_village() {
+        let set = [Card::Smithy, Card::Cellar, Card::Market, Card::Militia, Card::Mine,
+            Card::Moat, Card::Remodel, Card::Woodcutter, Card::Workshop, Card::Cellar];
+        assert!(kingdom_needs_village(&set));
+        assert!(!kingdom_needs_village(&lists::FIRST_SET));
+    }
+    #[test]
+    fn balance_of_sets() {
+        assert_eq!(kingdom_balance(&lists::FIRST_SET),
+            KingdomBalance { plus_buy: true, plus_action: true, trashing: true, attacks: true });
+        let set = [Card::Smithy, Card::Moat, Card::Workshop, Card::Smithy, Card::Moat,
+            Card::Workshop, Card::Smithy, Card::Moat, Card::Workshop, Card::Remodel];
+        assert_eq!(kingdom_balance(&set),
+            KingdomBalance { plus_buy: false, plus_action: false, trashing: true, attacks: false });
+        let mut chapel = set;
+        chapel[9] = Card::Chapel;
+        assert!(kingdom_balance(&chapel).trashing);
+        chapel[9] = Card::Village;
+        assert!(!kingdom_balance(&chapel).trashing);
+    }
+}
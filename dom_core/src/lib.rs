@@ -1,25 +1,39 @@
-#![feature(vec_remove_item)]
-
 #[macro_use]
 extern crate enum_map;
 extern crate rand;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
+pub mod agent;
 pub mod card;
+pub mod kingdom;
+#[cfg(feature = "serde")]
+pub mod save;
+pub mod sim;
 mod state;
 mod rules;
 
-pub use card::{Card, CardSet};
+pub use card::{Card, CardSet, ParseCardError};
 pub use rules::{Players, Rules};
-pub use state::{BoardState, Mutations, Player, Mutation, Reveal, PlayerSet, PlayerState, PlayerPhase};
+pub use state::{BoardState, BoardStateBuilder, Mutations, Player, Mutation, Reveal, PlayerSet, PlayerState, PlayerPhase, ValidationError, ApplyError, obscure_mutations};
 
-use state::RNGSeed;
+pub use state::RNGSeed;
 
-use rand::random;
+use rand::{random, Rng};
+
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+use std::sync::Arc;
 
 /// Current state of the game
 ///
 /// This indirectly implies what actions are valid against the game
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
 pub enum State {
     /// Action phase of the current player
     ///
@@ -28,6 +42,16 @@ pub enum State {
     ActionPhase,
     /// Buy phase of the current player
     BuyPhase,
+    /// A player must resolve a decision before the game can continue
+    ///
+    /// See `Game::pending_decision` for what needs deciding.
+    PendingDecision,
+    /// The game has finished and no further actions are possible
+    GameOver,
+    /// No player has a turn, such as on a board that is still being set up
+    ///
+    /// No actions are possible until a player's turn begins.
+    NotStarted,
 }
 
 // TODO: Is there a better way to encode what actions are permissible by different states?
@@ -39,10 +63,252 @@ pub enum State {
 /// there are choices.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Action {
+    /// Play an action card from hand during the action phase
+    PlayAction(Card),
     /// End action phase
     EndAction,
+    /// Play a treasure from hand during the buy phase, adding its value to the available gold
+    PlayTreasure(Card),
+    /// Play every treasure in hand during the buy phase
+    PlayAllTreasures,
+    /// Buy a card from the supply during the buy phase
+    Buy(Card),
     /// End buy phase
     EndBuy,
+    /// Active player resigns, ending the game
+    Concede,
+}
+
+/// Reasons `Game::act` can refuse an action
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActionError {
+    /// The action is not allowed while the game is in the given state
+    WrongState(State),
+    /// The effects of playing the card are not implemented, or it is not an action
+    CannotPlay(Card),
+    /// The active player does not have the card in hand
+    NotInHand(Card),
+    /// The active player has no actions left
+    NoActions,
+    /// The active player has no buys left
+    NoBuys,
+    /// The card costs more than the active player has
+    NotEnoughGold { cost: u32, gold: u32 },
+    /// The card has no pile in the supply
+    NotInSupply(Card),
+    /// The supply pile of the card is empty
+    PileEmpty(Card),
+    /// The board rejected the mutations the action required
+    Rejected,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ActionError::WrongState(State::ActionPhase) => write!(f, "Not possible in the action phase"),
+            ActionError::WrongState(State::BuyPhase) => write!(f, "Not possible in the buy phase"),
+            ActionError::WrongState(State::PendingDecision) => write!(f, "Not possible until the pending decision is resolved"),
+            ActionError::WrongState(State::GameOver) => write!(f, "Not possible once the game is over"),
+            ActionError::WrongState(State::NotStarted) => write!(f, "Not possible before a turn has begun"),
+            ActionError::CannotPlay(card) => write!(f, "{} cannot be played", card),
+            ActionError::NotInHand(card) => write!(f, "{} is not in hand", card),
+            ActionError::NoActions => write!(f, "No actions left"),
+            ActionError::NoBuys => write!(f, "No buys left"),
+            ActionError::NotEnoughGold { cost, gold } =>
+                write!(f, "Costs {} but only {} gold available", cost, gold),
+            ActionError::NotInSupply(card) => write!(f, "{} is not in the supply", card),
+            ActionError::PileEmpty(card) => write!(f, "{} pile is empty", card),
+            ActionError::Rejected => write!(f, "Board rejected the action"),
+        }
+    }
+}
+
+/// Reasons `Game::simulate` stopped before the game was over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimulateError {
+    /// The game was still going after the given number of turns
+    TurnLimit(u32),
+    /// An agent chose an action or resolution that was refused
+    Refused(ActionError),
+}
+
+impl fmt::Display for SimulateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SimulateError::TurnLimit(turns) => write!(f, "Game did not finish within {} turns", turns),
+            SimulateError::Refused(err) => write!(f, "Agent chose a refused action: {}", err),
+        }
+    }
+}
+
+/// Choice a player must make to finish resolving a card
+///
+/// Decisions are answered with `Game::resolve_decision`, passing a `Choice` of the shape given
+/// for each decision.
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Decision {
+    /// Discard up to `max` cards from hand and then draw as many as were discarded
+    ///
+    /// Answered with `Choice::Cards`.
+    CellarDiscard { player: Player, max: u32 },
+    /// Trash up to `max` cards from hand
+    ///
+    /// Answered with `Choice::Cards`.
+    TrashUpTo { player: Player, max: u32 },
+    /// Gain a card from the supply costing up to `max_cost`
+    ///
+    /// Answered with `Choice::Card`, or `Choice::None` only when no pile has a card costing that
+    /// little left.
+    Gain { player: Player, max_cost: u32 },
+    /// Discard down to `to` cards in hand
+    ///
+    /// Answered with `Choice::Cards`.
+    MilitiaDiscard { player: Player, to: u32 },
+    /// Optionally reveal a Moat from hand to be unaffected by the `attack` being played
+    ///
+    /// Answered with `Choice::Bool`, true to reveal. The attack resolves once every player able
+    /// to react has answered.
+    ReactToAttack { player: Player, attack: Card },
+    /// Optionally trash a treasure from hand to gain a treasure costing up to 3 more into hand
+    ///
+    /// Answered with `Choice::Cards` of the trashed card followed by the gained card, or
+    /// `Choice::None` to decline.
+    MineUpgrade { player: Player },
+    /// Trash a card from hand, to then gain a card costing up to 2 more
+    ///
+    /// Answered with `Choice::Card`.
+    RemodelTrash { player: Player },
+}
+
+/// Answer to a `Decision`
+///
+/// Each decision only accepts the shapes of answer it documents, anything else is rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Choice {
+    /// A selection of cards, such as the cards to discard or trash
+    Cards(Vec<Card>),
+    /// A single card, such as the card to gain or trash
+    Card(Card),
+    /// Whether to take an optional effect, such as revealing a Moat
+    Bool(bool),
+    /// Decline, or choose nothing
+    None,
+}
+
+impl Decision {
+    /// Player that must make the decision
+    pub fn player(&self) -> Player {
+        match *self {
+            Decision::CellarDiscard { player, .. } => player,
+            Decision::TrashUpTo { player, .. } => player,
+            Decision::Gain { player, .. } => player,
+            Decision::MilitiaDiscard { player, .. } => player,
+            Decision::ReactToAttack { player, .. } => player,
+            Decision::MineUpgrade { player } => player,
+            Decision::RemodelTrash { player } => player,
+        }
+    }
+    /// The only valid answer to the decision in `game`, if there is exactly one
+    ///
+    /// Forced decisions are resolved automatically as they are made, so they are never pending.
+    pub fn is_forced(&self, game: &Game) -> Option<Choice> {
+        self.forced_choice(game.board_state())
+    }
+    fn forced_choice(&self, state: &BoardState) -> Option<Choice> {
+        let hand = state.get_player(self.player())?.hand_iter().collect::<Vec<Option<Card>>>();
+        match *self {
+            Decision::CellarDiscard { .. } | Decision::TrashUpTo { .. } if hand.is_empty() =>
+                Some(Choice::Cards(Vec::new())),
+            Decision::MilitiaDiscard { to, .. } if hand.len() <= to as usize => Some(Choice::Cards(Vec::new())),
+            Decision::Gain { max_cost, .. } => {
+                match *state.supply_cards_costing(max_cost, false) {
+                    [] => Some(Choice::None),
+                    [card] => Some(Choice::Card(card)),
+                    _ => None,
+                }
+            },
+            // Declining is the only option without a treasure in hand
+            Decision::MineUpgrade { .. } if hand.iter().all(|c| c.is_some_and(|c| !c.is_treasure())) =>
+                Some(Choice::None),
+            Decision::RemodelTrash { .. } => {
+                match *hand {
+                    [Some(first), ..] if hand.iter().all(|c| *c == Some(first)) => Some(Choice::Card(first)),
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of a finished game
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameResult {
+    /// Players that won the game. Multiple winners are tied
+    pub winners: Vec<Player>,
+    /// Players that lost the game
+    pub losers: Vec<Player>,
+}
+
+/// Notable occurrences in a game that are delivered to observers
+///
+/// Events never change the board state, they are derived from the mutations that were applied
+/// so that consumers do not each have to re-derive them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A supply pile that had cards in it is now empty
+    PileEmptied(Card),
+    /// The game has finished with the given result
+    ///
+    /// This is always the last event of a game.
+    GameEnded(GameResult),
+}
+
+/// Whether the effects of playing `card` are implemented
+fn can_play(card: Card) -> bool {
+    matches!(card, Card::Cellar | Card::Chapel | Card::Festival | Card::Laboratory | Card::Market | Card::Militia | Card::Mine | Card::Moat | Card::Remodel
+        | Card::Smithy | Card::Village | Card::Woodcutter | Card::Workshop)
+}
+
+/// Outcome of the game on `state` if it has finished
+///
+/// A player conceding loses to everyone else. Otherwise once the supply runs out the best ranked
+/// players win, see `BoardState::rankings`.
+fn game_result(state: &BoardState) -> Option<GameResult> {
+    if let Some(conceded) = state.conceded() {
+        return Some(GameResult {
+            winners: Player::iter_players(state.num_players()?)
+                .cloned()
+                .filter(|p| *p != conceded)
+                .collect(),
+            losers: vec![conceded],
+        });
+    }
+    if !state.is_game_over() {
+        return None;
+    }
+    let rankings = state.rankings();
+    let rank = |&(p, points): &(Player, i32)| (points, state.get_player(p).map(|p| p.get_turns_taken()));
+    let best = rank(rankings.first()?);
+    let (winners, losers) = rankings.iter()
+        .partition::<Vec<&(Player, i32)>, _>(|r| rank(r) == best);
+    Some(GameResult {
+        winners: winners.iter().map(|r| r.0).collect(),
+        losers: losers.iter().map(|r| r.0).collect(),
+    })
+}
+
+/// Determine the events caused by moving from the `before` to the `after` board state
+fn derive_events(before: &BoardState, after: &BoardState) -> Vec<Event> {
+    let mut events: Vec<Event> = after.supply_stacks()
+        .filter(|(card, count)| **count == 0 && before.count_supply(*card) > Some(0))
+        .map(|(card, _)| Event::PileEmptied(card))
+        .collect();
+    if game_result(before).is_none() {
+        events.extend(game_result(after).map(Event::GameEnded));
+    }
+    events
 }
 
 /// Holds an in progress game update
@@ -54,6 +320,10 @@ pub enum Action {
 struct Update<'a> {
     state: BoardState,
     updates: Mutations,
+    /// Decisions created by the update, to be resolved before any already pending
+    decisions: Vec<Decision>,
+    /// Whether the update resolves the first pending decision of `game`
+    resolves: bool,
     game: &'a mut Game,
 }
 
@@ -62,6 +332,8 @@ impl<'a> From<&'a mut Game> for Update<'a> {
         Update {
             state: game.state.clone(),
             updates: Vec::new(),
+            decisions: Vec::new(),
+            resolves: false,
             game: game,
         }
     }
@@ -69,27 +341,234 @@ impl<'a> From<&'a mut Game> for Update<'a> {
 
 impl<'a> Update<'a> {
     fn try_append(&mut self, mutation: Mutation) -> Option<()> {
-        if let Some(state) = self.state.clone().mutate(mutation) {
-            self.state = state;
-            self.updates.push(mutation);
-            Some(())
-        } else {
-            None
+        self.state.mutate_in_place(&mutation)?;
+        // Engine bugs show up here, so check the same invariants as `BoardState::mutate_multi`
+        #[cfg(feature = "debug_invariants")]
+        {
+            if let Err(err) = self.state.validate() {
+                panic!("Invariant violated after {:?}: {}", mutation, err);
+            }
         }
+        self.updates.push(mutation);
+        Some(())
     }
     fn apply(self) -> Mutations {
+        let events = derive_events(&self.game.state, &self.state);
+        self.game.record(&self.updates);
         self.game.state = self.state;
+        if self.resolves {
+            self.game.pending.remove(0);
+        }
+        self.game.pending.splice(0..0, self.decisions);
+        self.game.notify(&events);
+        self.game.publish(&self.updates);
         self.updates
     }
+    /// Draw the top card of the deck of `player`, shuffling the discard first if needed
+    ///
+    /// Fails without appending anything if there is nothing to draw, which happens when both the
+    /// deck and discard are empty. Effects that draw treat this as drawing nothing.
     fn try_draw_card(&mut self, player: Player) -> Option<()> {
-        // Attempt to shuffle + reveal + draw
-        self.try_append(Mutation::ShuffleDiscard(player));
+        let p = self.state.get_player(player)?;
+        if p.draw_iter().next().is_none() {
+            if !self.game.auto_shuffle || p.discard_iter().next().is_none() {
+                return None;
+            }
+            self.try_append(Mutation::ShuffleDiscard(player))?;
+        }
         if let Some(card) = self.state.get_player(player).and_then(|p| p.draw_iter().next()) {
-            self.try_append(Mutation::DrawCard(player, card))
+            self.try_append(Mutation::DrawCard(player, card))?;
+            if self.game.open && card.is_some() {
+                // Reveal the whole hand, as a reveal only establishes the hand contains at least those cards
+                let hand = self.state.get_player(player)?.known_hand();
+                self.try_append(Mutation::RevealHandCards(player, Some(hand), Reveal::All))
+            } else {
+                Some(())
+            }
         } else {
             None
         }
     }
+    /// Gain a card from the supply to the discard of `player`
+    ///
+    /// Gaining from an empty pile is legal and does nothing, so effects that must gain a card
+    /// (such as a Curse) still succeed once the pile runs out. Gaining a card that was never in
+    /// the supply fails.
+    fn gain_card(&mut self, player: Player, card: Card) -> Option<()> {
+        match self.state.count_supply(card)? {
+            0 => Some(()),
+            _ => self.try_append(Mutation::GainCard(player, card)),
+        }
+    }
+    /// Play a treasure from the hand of `player`, adding its value to their gold
+    fn play_treasure(&mut self, player: Player, card: Card) -> Option<()> {
+        let gold = self.state.get_player(player)?.get_gold();
+        self.try_append(Mutation::PlayCard(player, card))?;
+        self.try_append(Mutation::SetGold(player, gold + card.treasure_value()))
+    }
+    /// Play the action `card` from the hand of `player`
+    ///
+    /// Only the `Card::bonuses` of the card are resolved. Cards drawn are drawn one at a time
+    /// and a draw failing, as the deck and discard are empty, does not fail the play.
+    fn play_action(&mut self, player: Player, card: Card) -> Option<()> {
+        let actions = self.state.get_player(player)?.get_actions();
+        if actions == 0 || !card.is_action() {
+            return None;
+        }
+        self.try_append(Mutation::PlayCard(player, card))?;
+        self.try_append(Mutation::SetActions(player, actions - 1))?;
+        let bonuses = card.bonuses();
+        for _ in 0..bonuses.cards {
+            self.try_draw_card(player);
+        }
+        let current = self.state.get_player(player)?.clone();
+        if bonuses.actions > 0 {
+            self.try_append(Mutation::SetActions(player, current.get_actions() + bonuses.actions))?;
+        }
+        if bonuses.buys > 0 {
+            self.try_append(Mutation::SetBuys(player, current.get_buys() + bonuses.buys))?;
+        }
+        if bonuses.coins > 0 {
+            self.try_append(Mutation::SetGold(player, current.get_gold() + bonuses.coins))?;
+        }
+        match card {
+            Card::Cellar => {
+                let max = self.state.get_player(player)?.hand_size() as u32;
+                self.decide(Decision::CellarDiscard { player, max })?;
+            },
+            Card::Chapel => self.decide(Decision::TrashUpTo { player, max: 4 })?,
+            Card::Workshop => self.decide(Decision::Gain { player, max_cost: 4 })?,
+            Card::Mine => self.decide(Decision::MineUpgrade { player })?,
+            // With nothing to trash there is nothing to remodel
+            Card::Remodel if self.state.get_player(player)?.hand_size() > 0 =>
+                self.decide(Decision::RemodelTrash { player })?,
+            Card::Militia => {
+                let players = self.state.num_players()?;
+                let reactors = self.state.players_with_reaction(card);
+                if reactors == PlayerSet::empty() {
+                    self.attack(player, card)?;
+                }
+                for other in reactors.iter_from(player.next(players), players) {
+                    self.decide(Decision::ReactToAttack { player: other, attack: card })?;
+                }
+            },
+            _ => {},
+        }
+        Some(())
+    }
+    /// Resolve the attack `card` played by `player` against every unprotected opponent
+    fn attack(&mut self, player: Player, card: Card) -> Option<()> {
+        let players = self.state.num_players()?;
+        for other in PlayerSet::others(player, players).iter_from(player.next(players), players) {
+            if self.state.get_player(other)?.is_protected() {
+                continue;
+            }
+            if card == Card::Militia {
+                self.decide(Decision::MilitiaDiscard { player: other, to: 3 })?;
+            }
+        }
+        Some(())
+    }
+    /// Have a player make `decision`, answering it straight away if it is forced
+    fn decide(&mut self, decision: Decision) -> Option<()> {
+        match decision.forced_choice(&self.state) {
+            Some(choice) => self.answer(decision, &choice),
+            None => {
+                self.decisions.push(decision);
+                Some(())
+            },
+        }
+    }
+    /// Resolve the first pending decision of the game with `choice`
+    fn resolve(&mut self, decision: Decision, choice: &Choice) -> Option<()> {
+        if self.game.pending.first() != Some(&decision) {
+            return None;
+        }
+        self.resolves = true;
+        self.answer(decision, choice)
+    }
+    /// Carry out the effects of answering `decision` with `choice`
+    fn answer(&mut self, decision: Decision, choice: &Choice) -> Option<()> {
+        match (decision, choice) {
+            (Decision::CellarDiscard { player, max }, Choice::Cards(cards)) => {
+                if cards.len() as u32 > max {
+                    return None;
+                }
+                for card in cards {
+                    self.try_append(Mutation::DiscardHand(player, *card))?;
+                }
+                for _ in cards {
+                    self.try_draw_card(player);
+                }
+                Some(())
+            },
+            (Decision::TrashUpTo { player, max }, Choice::Cards(cards)) => {
+                if cards.len() as u32 > max {
+                    return None;
+                }
+                for card in cards {
+                    self.try_append(Mutation::TrashHand(player, *card))?;
+                }
+                Some(())
+            },
+            (Decision::Gain { player, max_cost }, Choice::Card(card)) => {
+                if card.cost() > max_cost || self.state.count_supply(*card)? == 0 {
+                    return None;
+                }
+                self.gain_card(player, *card)
+            },
+            // With nothing cheap enough left there is nothing to gain
+            (Decision::Gain { max_cost, .. }, Choice::None)
+                if self.state.supply_cards_costing(max_cost, false).is_empty() => Some(()),
+            (Decision::MilitiaDiscard { player, to }, Choice::Cards(cards)) => {
+                if self.state.get_player(player)?.hand_size().saturating_sub(to as usize) != cards.len() {
+                    return None;
+                }
+                for card in cards {
+                    self.try_append(Mutation::DiscardHand(player, *card))?;
+                }
+                Some(())
+            },
+            (Decision::ReactToAttack { player, attack }, Choice::Bool(reveal)) => {
+                if *reveal {
+                    let moat: CardSet = Some(Card::Moat).into_iter().collect();
+                    self.try_append(Mutation::RevealHandCards(player, Some(moat), Reveal::All))?;
+                    self.try_append(Mutation::SetProtected(player, true))?;
+                }
+                // The attack only happens once the last player has had the chance to react
+                match self.game.pending.get(1) {
+                    Some(Decision::ReactToAttack { .. }) => Some(()),
+                    _ => self.attack(self.state.active_player(), attack),
+                }
+            },
+            (Decision::MineUpgrade { .. }, Choice::None) => Some(()),
+            (Decision::MineUpgrade { player }, Choice::Cards(cards)) => {
+                match **cards {
+                    [trash, gain] if trash.is_treasure() && gain.is_treasure() && gain.cost() <= trash.cost() + 3 => {
+                        self.try_append(Mutation::TrashHand(player, trash))?;
+                        self.try_append(Mutation::GainToHand(player, gain))
+                    },
+                    _ => None,
+                }
+            },
+            (Decision::RemodelTrash { player }, Choice::Card(card)) => {
+                self.try_append(Mutation::TrashHand(player, *card))?;
+                self.decide(Decision::Gain { player, max_cost: card.cost() + 2 })
+            },
+            // Every other answer has the wrong shape for the decision
+            _ => None,
+        }
+    }
+    /// Buy `card` for `player`, paying for it with a buy and its cost in gold
+    fn buy_card(&mut self, player: Player, card: Card) -> Option<()> {
+        let (buys, gold) = self.state.get_player(player).map(|p| (p.get_buys(), p.get_gold()))?;
+        if self.state.count_supply(card)? == 0 {
+            return None;
+        }
+        self.try_append(Mutation::GainCard(player, card))?;
+        self.try_append(Mutation::SetBuys(player, buys.checked_sub(1)?))?;
+        self.try_append(Mutation::SetGold(player, gold.checked_sub(card.cost())?))
+    }
     fn begin_turn(&mut self, player: Player) -> Option<()> {
         // end the current players turn if neccessary
         let last_active = self.state.active_player();
@@ -98,11 +577,20 @@ impl<'a> Update<'a> {
             .map(|_|
                 self.try_append(Mutation::SetPhase(last_active, PlayerPhase::NotTurn))
             );
+        // protection from a Moat only lasts for the turn it was revealed in
+        let protected = Player::iter_players(self.state.num_players()?)
+            .cloned()
+            .filter(|p| self.state.get_player(*p).is_some_and(|p| p.is_protected()))
+            .collect::<Vec<Player>>();
+        for p in protected {
+            self.try_append(Mutation::SetProtected(p, false))?;
+        }
         self.try_append(Mutation::ChangeTurn(player))?;
         self.try_append(Mutation::SetPhase(player, PlayerPhase::Action))?;
         self.try_append(Mutation::SetBuys(player, 1))?;
         self.try_append(Mutation::SetActions(player, 1))?;
-        self.try_append(Mutation::SetGold(player, 0))
+        self.try_append(Mutation::SetGold(player, 0))?;
+        self.try_append(Mutation::ClearGained(player))
     }
     fn end_turn(&mut self, player: Player) -> Option<()>{
         // Create a copy of the hand to get around borrowing whilst updating problems
@@ -114,7 +602,7 @@ impl<'a> Update<'a> {
         // discard played cards
         self.try_append(Mutation::DiscardPlayed(player))?;
         // draw a new hand. These draws can fail as our deck may be too small
-        for _ in 0..5 {
+        for _ in 0..self.state.get_player(player)?.get_next_hand_size() {
             self.try_draw_card(player);
         }
         // change phase
@@ -122,12 +610,89 @@ impl<'a> Update<'a> {
     }
 }
 
+/// Batch of mutations in the undo history of a game, linked to the batch before it
+///
+/// Clones of a game share their history, each new batch only linking to the earlier ones, so
+/// copying a game does not copy its log.
+#[derive(Debug)]
+struct History {
+    mutations: Mutations,
+    /// Decisions that were pending before the batch was applied
+    pending: Vec<Decision>,
+    previous: Option<Arc<History>>,
+}
+
+impl History {
+    /// Every batch up to and including this one, oldest first
+    fn batches(&self) -> Vec<&History> {
+        let mut batches = vec![self];
+        while let Some(previous) = batches[batches.len() - 1].previous.as_ref() {
+            batches.push(previous);
+        }
+        batches.reverse();
+        batches
+    }
+}
+
+/// Unlinks the earlier batches one at a time, as dropping a long history recursively could
+/// overflow the stack.
+impl Drop for History {
+    fn drop(&mut self) {
+        let mut previous = self.previous.take();
+        while let Some(history) = previous {
+            previous = Arc::try_unwrap(history).ok().and_then(|mut history| history.previous.take());
+        }
+    }
+}
+
 /// Defines and runs the rules and logic of a dominion game
 ///
 /// Internally has a `BoardState` and performs actions against it.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Game {
     state: BoardState,
+    observers: Vec<Sender<Event>>,
+    subscribers: Vec<Sender<Mutation>>,
+    /// Whether every draw is publicly revealed
+    open: bool,
+    /// Whether draws from an empty deck shuffle the discard, see `Rules::auto_shuffle`
+    auto_shuffle: bool,
+    /// Decisions waiting to be resolved, the first being the next to resolve
+    pending: Vec<Decision>,
+    /// Board that the first batch in `history` was applied to
+    base: Arc<BoardState>,
+    /// Last batch of mutations applied to the game, if any can be undone
+    history: Option<Arc<History>>,
+}
+
+/// Observers and subscribers are not carried over to a clone so that speculative copies of a game, such as
+/// those made when searching, do not generate events. The undo history is shared with the clone
+/// rather than copied.
+impl Clone for Game {
+    fn clone(&self) -> Game {
+        Game {
+            state: self.state.clone(),
+            observers: Vec::new(),
+            subscribers: Vec::new(),
+            open: self.open,
+            auto_shuffle: self.auto_shuffle,
+            pending: self.pending.clone(),
+            base: self.base.clone(),
+            history: self.history.clone(),
+        }
+    }
+}
+
+/// Games hash by their board, whose turn it is and the phase, but not the RNG, so that a game
+/// reached through different mutations hashes the same. This is intended for transposition
+/// tables when searching.
+impl Hash for Game {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state.hash(state);
+        self.state.active_player().hash(state);
+        self.state().hash(state);
+        self.pending.hash(state);
+    }
 }
 
 impl Game {
@@ -135,95 +700,429 @@ impl Game {
         Mutation::AddStack(c, c.starting_count(players))
     }
     pub fn from_state(state: BoardState) -> Option<Game> {
-        Some(Game {state: state})
+        Some(Game {state: state, observers: Vec::new(), subscribers: Vec::new(), open: false, auto_shuffle: true, pending: Vec::new(),
+            base: Arc::new(BoardState::new(None)), history: None})
     }
     pub fn from_mutations(mutations: &Mutations) -> Option<Game> {
         BoardState::from_mutations(mutations).and_then(Self::from_state)
     }
+    /// Check that a game log can be replayed from an empty board
+    ///
+    /// Unlike `from_mutations` the position and mutation that first fails are reported.
+    pub fn validate_log(mutations: &Mutations) -> Result<(), ApplyError> {
+        BoardState::new(None).try_mutate_multi(mutations).map(|_| ())
+    }
+    /// Replay a game log up to the start of the given turn
+    ///
+    /// Turns are counted by `ChangeTurn` mutations, with the first turn of the game being turn 1.
+    /// The returned game has had the turn set up, but none of it has been played. Returns `None`
+    /// if the log does not reach the turn or cannot be applied.
+    pub fn replay_to_turn(mutations: &Mutations, turn: u32) -> Option<Game> {
+        let start = mutations.iter()
+            .enumerate()
+            .filter(|(_, m)| matches!(m, Mutation::ChangeTurn(_)))
+            .map(|(index, _)| index)
+            .nth((turn as usize).checked_sub(1)?)?;
+        // Turn set up finishes by clearing the gained cards
+        let end = mutations[start..].iter()
+            .position(|m| matches!(m, Mutation::ClearGained(_)))?;
+        Self::from_mutations(&mutations[..=start + end].to_vec())
+    }
     /// Create new game with given rules
-    fn new_from_seed(rules: Rules, seed: RNGSeed) -> (Game, Mutations) {
+    ///
+    /// The seed determines every shuffle, so games created from the same rules and seed, such as
+    /// one from `BoardState::seed` of another game, are identical.
+    ///
+    /// Panics if the starting player is not one of the players in the game.
+    pub fn new_from_seed(rules: Rules, seed: RNGSeed) -> (Game, Mutations) {
+        Self::new_from_seed_with(rules, seed, false)
+    }
+    fn new_from_seed_with(rules: Rules, seed: RNGSeed, open: bool) -> (Game, Mutations) {
+        assert!(Player::iter_players(rules.players).any(|p| *p == rules.starting_player),
+            "Starting player {:?} is not in a game of {:?} players", rules.starting_player, rules.players);
         let mut game =
             Game {
                 state: BoardState::new(Some(seed)),
+                observers: Vec::new(),
+                subscribers: Vec::new(),
+                open,
+                auto_shuffle: rules.auto_shuffle,
+                pending: Vec::new(),
+                base: Arc::new(BoardState::new(None)),
+                history: None,
             };
         let mutations;
         {
             let mut up = Update::from(&mut game);
             up.try_append(Mutation::SetPlayers(rules.players));
-            let stacks = card::lists::BASE_TREASURE.iter()
-                .chain(card::lists::BASE_VICTORY.iter())
-                .chain(rules.set.iter())
-                .chain([Card::Curse].iter());
-            for card in stacks {
-                up.try_append(Mutation::AddStack(*card, card.starting_count(rules.players)));
+            for (card, count) in rules.supply_piles() {
+                up.try_append(Mutation::AddStack(card, count));
             }
             for player in Player::iter_players(rules.players) {
-                for _ in 0..3 {
-                    up.try_append(Mutation::GainCard(*player, Card::Estate));
-                }
-                for _ in 0..7 {
-                    up.try_append(Mutation::GainCard(*player, Card::Copper));
+                for &(card, count) in card::lists::STARTING_DECK.iter() {
+                    for _ in 0..count {
+                        up.try_append(Mutation::GainCard(*player, card))
+                            .expect("Supply piles hold enough cards to deal the starting decks");
+                    }
                 }
+                // Starting cards do not count as having been gained on a turn
+                up.try_append(Mutation::ClearGained(*player));
                 up.try_append(Mutation::ShuffleDiscard(*player));
                 for _ in 0..5 {
                     up.try_draw_card(*player);
                 }
             }
-            up.begin_turn(Player::P0);
+            up.try_append(Mutation::BeginGame);
+            up.begin_turn(rules.starting_player);
             mutations = up.apply();
         }
+        // Setting up the game cannot be undone
+        game.history = None;
         (game, mutations)
     }
-    fn new(rules: Rules) -> (Game, Mutations) {
-        let seed = [
+    fn random_seed() -> RNGSeed {
+        [
             random(),random(),random(),random(),random(),random(),random(),random(),
             random(),random(),random(),random(),random(),random(),random(),random(),
             random(),random(),random(),random(),random(),random(),random(),random(),
-            random(),random(),random(),random(),random(),random(),random(),random()];
+            random(),random(),random(),random(),random(),random(),random(),random()]
+    }
+    fn new(rules: Rules) -> (Game, Mutations) {
+        Self::new_from_seed(rules, Self::random_seed())
+    }
+    /// Create a new game seeded from an existing RNG
+    ///
+    /// The same RNG state always produces the same game, so callers that already have an RNG can
+    /// fork it deterministically.
+    pub fn new_with_rng<R: Rng>(rules: Rules, rng: &mut R) -> (Game, Mutations) {
+        let mut seed: RNGSeed = [0; 32];
+        rng.fill(&mut seed);
         Self::new_from_seed(rules, seed)
     }
+    /// Create a new game without any hidden information
+    ///
+    /// Every card drawn is publicly revealed as soon as it is drawn, so every perspective of the
+    /// game knows every hand. This is useful for tutorials, debugging and solvers.
+    pub fn new_open(rules: Rules) -> (Game, Mutations) {
+        Self::new_from_seed_with(rules, Self::random_seed(), true)
+    }
     /// Initialize 'First Game' layout
     pub fn new_first_game(players: Players) -> (Game, Mutations) {
-        Self::new( Rules {
-            players: players,
-            set: card::lists::FIRST_SET,
-        })
+        Self::new(Rules::new(players, card::lists::FIRST_SET))
     }
     pub fn state(&self) -> State {
-        let active = self.board_state().get_player(self.board_state().active_player()).unwrap();
-        match active.get_phase() {
-            PlayerPhase::Action => State::ActionPhase,
-            PlayerPhase::Buy => State::BuyPhase,
-            _ => unimplemented!(),
+        if self.result().is_some() || self.state.is_game_over() {
+            return State::GameOver;
+        }
+        if !self.pending.is_empty() {
+            return State::PendingDecision;
+        }
+        match self.state.active_player_state().map(PlayerState::get_phase) {
+            Some(PlayerPhase::Action) => State::ActionPhase,
+            Some(PlayerPhase::Buy) => State::BuyPhase,
+            Some(PlayerPhase::NotTurn) | None => State::NotStarted,
         }
     }
     pub fn board_state(&self) -> &BoardState {
         &self.state
     }
+    /// Direct mutable access to the board, bypassing mutations
+    ///
+    /// This is for scenario editors and debuggers. Nothing checks the edits, so a tool should call
+    /// `BoardState::validate` once it is done. The edits cannot be undone and the undo history
+    /// is discarded.
+    #[cfg(feature = "scenario-edit")]
+    pub fn board_state_mut(&mut self) -> &mut BoardState {
+        self.history = None;
+        &mut self.state
+    }
+    /// State of the player whose turn it is
+    ///
+    /// Panics if the game has no players.
+    pub fn active_player_state(&self) -> &PlayerState {
+        self.state.active_player_state().expect("Game has no players")
+    }
+    /// Distinct action cards the active player could play right now
+    ///
+    /// Empty outside of the action phase or when the player has no actions left.
+    pub fn playable_actions_in_hand(&self) -> Vec<Card> {
+        if self.state() != State::ActionPhase || self.active_player_state().get_actions() == 0 {
+            return Vec::new();
+        }
+        self.active_player_state().known_hand()
+            .count_iter()
+            .filter(|(card, count)| **count > 0 && card.is_action())
+            .map(|(card, _)| card)
+            .collect()
+    }
+    /// Whether the active player could play an action card right now
+    ///
+    /// When this is false in the action phase `Action::EndAction` is the only valid action, so
+    /// front ends and bots can end the phase without asking.
+    pub fn can_play_any_action(&self) -> bool {
+        self.valid_actions().iter().any(|action| matches!(action, Action::PlayAction(_)))
+    }
+    /// Every action `act` would currently accept
+    ///
+    /// Lists ending the phase first, then playing each distinct action or treasure card in hand
+    /// and then buying each affordable card in supply display order. `Action::Concede` is always
+    /// legal before the game is over and is not listed, nor is the `Action::PlayAllTreasures`
+    /// shorthand.
+    pub fn valid_actions(&self) -> Vec<Action> {
+        let state = self.state();
+        let hand = match state {
+            State::ActionPhase | State::BuyPhase => self.active_player_state().known_hand(),
+            _ => return Vec::new(),
+        };
+        let plays = hand.count_iter()
+            .filter(|(_, count)| **count > 0)
+            .flat_map(|(card, _)| vec![Action::PlayAction(card), Action::PlayTreasure(card)]);
+        let buys = self.state.supply_cards().into_iter().map(Action::Buy);
+        [Action::EndAction, Action::EndBuy].iter().cloned()
+            .chain(plays)
+            .chain(buys)
+            .filter(|action| self.check_action(state, *action).is_ok())
+            .collect()
+    }
+    /// Decision that must be resolved next, if any
+    pub fn pending_decision(&self) -> Option<Decision> {
+        self.pending.first().cloned()
+    }
+    /// Every decision waiting to be resolved, in the order they will be resolved
+    ///
+    /// Only the first can be resolved, but front ends can show the rest, such as every
+    /// opponent that will have to discard to a Militia.
+    pub fn pending_decisions(&self) -> &[Decision] {
+        &self.pending
+    }
+    /// Resolve the pending decision with `choice`
+    ///
+    /// `decision` must be the current `pending_decision`. Returns `None` if it is not, or if
+    /// the choice is the wrong shape or otherwise not a valid answer to it.
+    pub fn resolve_decision(&mut self, decision: Decision, choice: Choice) -> Option<Mutations> {
+        let mut up = Update::from(self);
+        up.resolve(decision, &choice)?;
+        Some(up.apply())
+    }
+    /// Key identifying the position of the game, see the `Hash` implementation
+    pub fn hash_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+    /// Outcome of the game if it has finished
+    pub fn result(&self) -> Option<GameResult> {
+        game_result(&self.state)
+    }
     /// Perform an action against the game
     ///
     /// If the action can be successfully performed the internal game state is updated
-    /// and the list of mutations that were performed is returned.
-    pub fn act(&mut self, action: Action) -> Option<Mutations> {
+    /// and the list of mutations that were performed is returned. Otherwise the game is
+    /// unchanged and the reason the action was refused is returned.
+    pub fn act(&mut self, action: Action) -> Result<Mutations, ActionError> {
         let state = self.state();
         let active = self.board_state().active_player();
+        self.check_action(state, action)?;
         let mut up = Update::from(self);
         match action {
-            Action::EndAction if state == State::ActionPhase => {up.try_append(Mutation::SetPhase(active,PlayerPhase::Buy)); Some(up.apply())},
-            Action::EndBuy if state == State::BuyPhase => {
+            Action::PlayAction(card) => up.play_action(active, card),
+            Action::EndAction => up.try_append(Mutation::SetPhase(active, PlayerPhase::Buy)),
+            Action::PlayTreasure(card) => up.play_treasure(active, card),
+            Action::PlayAllTreasures => {
+                let treasures: Vec<Card> = up.state.get_player(active).unwrap().known_hand()
+                    .into_iter()
+                    .filter(|card| card.is_treasure())
+                    .collect();
+                treasures.into_iter().try_for_each(|card| up.play_treasure(active, card))
+            },
+            Action::Buy(card) => up.buy_card(active, card),
+            Action::EndBuy => {
                 let next = active.next(up.state.num_players().unwrap());
-                up.end_turn(active)?;
-                up.begin_turn(next)?;
-                Some(up.apply())
+                up.end_turn(active).and_then(|_| up.begin_turn(next))
+            },
+            Action::Concede => up.try_append(Mutation::Concede(active)),
+        }.ok_or(ActionError::Rejected)?;
+        Ok(up.apply())
+    }
+    /// Reason `action` cannot be performed in `state`, if any
+    fn check_action(&self, state: State, action: Action) -> Result<(), ActionError> {
+        let wrong_state = Err(ActionError::WrongState(state));
+        match action {
+            Action::PlayAction(_) | Action::EndAction if state != State::ActionPhase => wrong_state,
+            Action::Buy(_) | Action::EndBuy | Action::PlayTreasure(_) | Action::PlayAllTreasures
+                if state != State::BuyPhase => wrong_state,
+            Action::Concede if state == State::GameOver || state == State::NotStarted => wrong_state,
+            Action::PlayAction(card) => {
+                let player = self.active_player_state();
+                if !can_play(card) {
+                    Err(ActionError::CannotPlay(card))
+                } else if player.get_actions() == 0 {
+                    Err(ActionError::NoActions)
+                } else if !player.known_hand().contains(card) {
+                    Err(ActionError::NotInHand(card))
+                } else {
+                    Ok(())
+                }
             },
-            _ => None
+            Action::PlayTreasure(card) => {
+                if !card.is_treasure() {
+                    Err(ActionError::CannotPlay(card))
+                } else if !self.active_player_state().known_hand().contains(card) {
+                    Err(ActionError::NotInHand(card))
+                } else {
+                    Ok(())
+                }
+            },
+            Action::Buy(card) => {
+                let player = self.active_player_state();
+                match self.state.count_supply(card) {
+                    None => Err(ActionError::NotInSupply(card)),
+                    Some(0) => Err(ActionError::PileEmpty(card)),
+                    _ if player.get_buys() == 0 => Err(ActionError::NoBuys),
+                    _ if player.get_gold() < card.cost() =>
+                        Err(ActionError::NotEnoughGold { cost: card.cost(), gold: player.get_gold() }),
+                    _ => Ok(()),
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+    /// Play the game to the end with an agent in each seat
+    ///
+    /// `agents` are indexed by player. Treasures are played automatically at the start of each
+    /// buy phase, so agents only choose what to buy. Agents that never end the game, such as ones
+    /// that never buy, make this loop forever. Fails if an agent chooses an invalid action or
+    /// resolution.
+    pub fn play_out(&mut self, agents: &mut [Box<dyn agent::Agent>]) -> Result<GameResult, ActionError> {
+        while self.state() != State::GameOver {
+            self.agent_step(agents)?;
         }
+        Ok(self.result().expect("Finished games have a result"))
+    }
+    /// Like `play_out`, but gives up once more than `max_turns` turns have started
+    ///
+    /// Returns the final board. This is intended for fuzzing with agents that may never finish.
+    pub fn simulate(&mut self, agents: &mut [Box<dyn agent::Agent>], max_turns: u32) -> Result<BoardState, SimulateError> {
+        while self.state() != State::GameOver {
+            if self.state.turn_number() > max_turns {
+                return Err(SimulateError::TurnLimit(max_turns));
+            }
+            self.agent_step(agents).map_err(SimulateError::Refused)?;
+        }
+        Ok(self.state.clone())
+    }
+    /// Have the agent whose input the game is waiting for make a single action or resolution
+    fn agent_step(&mut self, agents: &mut [Box<dyn agent::Agent>]) -> Result<(), ActionError> {
+        match self.state() {
+            state @ State::GameOver | state @ State::NotStarted => Err(ActionError::WrongState(state)),
+            State::PendingDecision => {
+                let decision = self.pending_decision().unwrap();
+                let player = decision.player();
+                let choice = agents[player as usize].choose_resolution(self, player, decision);
+                self.resolve_decision(decision, choice).map(|_| ()).ok_or(ActionError::Rejected)
+            },
+            state => {
+                if state == State::BuyPhase {
+                    self.auto_play_treasures();
+                }
+                let player = self.board_state().active_player();
+                let action = agents[player as usize].choose_action(self, player);
+                self.act(action).map(|_| ())
+            },
+        }
+    }
+    /// Move the active player from the action phase to the buy phase
+    ///
+    /// Does nothing, returning `None`, if the game is not in an action phase.
+    pub fn skip_to_buy_phase(&mut self) -> Option<Mutations> {
+        if self.state() != State::ActionPhase {
+            return None;
+        }
+        self.act(Action::EndAction).ok()
+    }
+    /// Play every treasure in the active player's hand
+    ///
+    /// This is a helper for harnesses whose players always play all their treasures, doing
+    /// `Action::PlayAllTreasures`. It does nothing outside of the buy phase.
+    pub fn auto_play_treasures(&mut self) -> Mutations {
+        self.act(Action::PlayAllTreasures).unwrap_or_default()
+    }
+    /// Apply mutations to a game maintained from the perspective of `viewer`
+    ///
+    /// The mutations are filtered by `Mutation::obscure_for` first, so anything hidden from
+    /// `viewer` is never learnt even if the full mutations are passed in.
+    pub fn apply_mutations_as(&mut self, mutations: &Mutations, viewer: Player) -> bool {
+        self.apply_mutations(&obscure_mutations(mutations.clone(), viewer))
     }
     pub fn apply_mutations(&mut self, mutations: &Mutations) -> bool {
-        match self.state.clone().mutate_multi(mutations) {
-            Some(new_state) => {self.state = new_state; true},
-            None => false,
+        self.try_apply_mutations(mutations).is_ok()
+    }
+    /// Apply mutations, describing which one failed if they cannot be applied
+    ///
+    /// Nothing is applied if any mutation fails.
+    pub fn try_apply_mutations(&mut self, mutations: &Mutations) -> Result<(), ApplyError> {
+        let new_state = self.state.clone().try_mutate_multi(mutations)?;
+        let events = derive_events(&self.state, &new_state);
+        self.record(mutations);
+        self.state = new_state;
+        self.notify(&events);
+        self.publish(mutations);
+        Ok(())
+    }
+    /// Revert the last batch of mutations applied to the game
+    ///
+    /// A batch is everything returned by a single `act`, `resolve_decision` or other update of
+    /// the game. The board is rebuilt by replaying every earlier batch, and the undone mutations
+    /// are returned. Returns `None` once there is nothing left to undo, which includes the setup
+    /// of a new game. Observers and subscribers are not told about the undo.
+    pub fn undo_last(&mut self) -> Option<Mutations> {
+        let last = self.history.take()?;
+        self.history = last.previous.clone();
+        let replay: Mutations = self.history.iter()
+            .flat_map(|history| history.batches())
+            .flat_map(|batch| batch.mutations.iter().cloned())
+            .collect();
+        self.state = (*self.base).clone().mutate_multi(&replay)
+            .expect("Replaying the history of a game failed");
+        self.pending = last.pending.clone();
+        Some(last.mutations.clone())
+    }
+    /// Remember a batch of mutations about to be applied to the current board
+    fn record(&mut self, mutations: &Mutations) {
+        if mutations.is_empty() {
+            return;
+        }
+        if self.history.is_none() {
+            self.base = Arc::new(self.state.clone());
         }
+        let previous = self.history.take();
+        self.history = Some(Arc::new(History { mutations: mutations.clone(), pending: self.pending.clone(), previous }));
+    }
+    /// Register a new observer of game events
+    ///
+    /// Events are sent for every update to the game after this call. Dropping the `Receiver`
+    /// unregisters the observer.
+    pub fn observe(&mut self) -> Receiver<Event> {
+        let (sender, receiver) = channel();
+        self.observers.push(sender);
+        receiver
+    }
+    /// Subscribe to the stream of mutations applied to the game
+    ///
+    /// Every mutation applied after this call is sent in order, allowing a spectator to rebuild
+    /// the board incrementally. No filtering is performed, so the stream contains all hidden
+    /// information. Dropping the `Receiver` unsubscribes.
+    pub fn subscribe(&mut self) -> Receiver<Mutation> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+    fn notify(&mut self, events: &[Event]) {
+        // Forget about any observers whose receiver has gone away
+        self.observers.retain(|observer| events.iter().all(|e| observer.send(e.clone()).is_ok()));
+    }
+    fn publish(&mut self, mutations: &[Mutation]) {
+        self.subscribers.retain(|subscriber| mutations.iter().all(|m| subscriber.send(m.clone()).is_ok()));
     }
 }
 
@@ -250,24 +1149,101 @@ mod tests {
         assert_eq!(g.board_state().count_supply(Card::Province), Some(12));
     }
     #[test]
+    fn victory_pile_size_override() {
+        let rules = Rules {
+            victory_pile_sizes: Some(enum_map! {
+                Card::Estate => 8,
+                Card::Duchy => 8,
+                Card::Province => 6,
+                _ => 100,
+            }),
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        let (g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        assert_eq!(g.board_state().count_supply(Card::Province), Some(6));
+        assert_eq!(g.board_state().count_supply(Card::Duchy), Some(8));
+        assert_eq!(g.board_state().count_supply(Card::Estate), Some(8));
+        // Overrides for non victory cards are ignored
+        assert_eq!(g.board_state().count_supply(Card::Gold), Some(30));
+        assert_eq!(g.board_state().count_supply(Card::Smithy), Some(10));
+    }
+    #[test]
+    fn estate_override_is_left_after_dealing() {
+        let rules = Rules {
+            victory_pile_sizes: Some(enum_map! { Card::Estate => 1, _ => 8 }),
+            ..Rules::new(Players::Four, card::lists::FIRST_SET)
+        };
+        assert_eq!(rules.starting_count(Card::Estate), 13);
+        let (g, _) = Game::new_from_seed(rules.clone(), DUMMY_SEED);
+        assert_eq!(g.board_state().count_supply(Card::Estate), Some(1));
+        // Every starting deck is still dealt in full
+        for player in Player::iter_players(Players::Four) {
+            assert_eq!(g.board_state().full_deck(*player).unwrap().0.count(Card::Estate), 3);
+        }
+        g.board_state().assert_conserved(&rules.card_pool());
+    }
+    #[test]
+    fn estates_dealt_from_supply() {
+        let g = Game::new_first_game(Players::Two).0;
+        assert_eq!(g.board_state().count_supply(Card::Estate), Some(8));
+        for player in Player::iter_players(Players::Two) {
+            let p = g.board_state().get_player(*player).unwrap();
+            let estates = p.hand_iter().chain(p.draw_iter()).filter(|c| *c == Some(Card::Estate)).count();
+            assert_eq!(estates, 3);
+        }
+    }
+    #[test]
+    fn curse_supply_after_setup() {
+        assert_eq!(Game::new_first_game(Players::Two).0.board_state().count_supply(Card::Curse), Some(10));
+        assert_eq!(Game::new_first_game(Players::Three).0.board_state().count_supply(Card::Curse), Some(20));
+        assert_eq!(Game::new_first_game(Players::Four).0.board_state().count_supply(Card::Curse), Some(30));
+    }
+    #[test]
+    fn second_player_starts() {
+        let rules = Rules {
+            starting_player: Player::P1,
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        let g = Game::new_from_seed(rules, DUMMY_SEED).0;
+        assert_eq!(g.board_state().active_player(), Player::P1);
+        assert_eq!(g.active_player_state().get_phase(), PlayerPhase::Action);
+        assert_eq!(g.board_state().get_player(Player::P0).unwrap().get_phase(), PlayerPhase::NotTurn);
+    }
+    #[test]
+    #[should_panic(expected = "Starting player P2 is not in a game of Two players")]
+    fn starting_player_must_exist() {
+        let rules = Rules {
+            starting_player: Player::P2,
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        Game::new_from_seed(rules, DUMMY_SEED);
+    }
+    #[test]
     fn rng_seeds_stable() {
-        let (g, _) = Game::new_from_seed(
-            Rules {
-                players: Players::Two,
-                set: card::lists::FIRST_SET,
-            },
-            DUMMY_SEED
-        );
-        let (g2, _) = Game::new_from_seed(
-            Rules {
-                players: Players::Two,
-                set: card::lists::FIRST_SET,
-            },
-            DUMMY_SEED
-        );
+        let (g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        let (g2, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
         assert_eq!(g.board_state(), g2.board_state());
     }
     #[test]
+    fn canonical_string_stable() {
+        let rules = || Rules::new(Players::Two, card::lists::FIRST_SET);
+        let mut g = Game::new_from_seed(rules(), DUMMY_SEED).0;
+        let g2 = Game::new_from_seed(rules(), DUMMY_SEED).0;
+        assert_eq!(g.board_state().canonical_string(), g2.board_state().canonical_string());
+        assert!(g.board_state().canonical_string().contains("Province=8"));
+        assert!(g.apply_mutations(&vec![Mutation::GainCard(Player::P1, Card::Province)]));
+        assert_ne!(g.board_state().canonical_string(), g2.board_state().canonical_string());
+    }
+    #[test]
+    fn hash_key_tracks_position() {
+        let rules = || Rules::new(Players::Two, card::lists::FIRST_SET);
+        let mut g = Game::new_from_seed(rules(), DUMMY_SEED).0;
+        let g2 = Game::new_from_seed(rules(), DUMMY_SEED).0;
+        assert_eq!(g.hash_key(), g2.hash_key());
+        g.act(Action::EndAction).unwrap();
+        assert_ne!(g.hash_key(), g2.hash_key());
+    }
+    #[test]
     fn new_game_first_turn_state() {
         let g = Game::new_first_game(Players::Two).0;
         assert_eq!(g.board_state().active_player(), Player::P0);
@@ -280,4 +1256,1055 @@ mod tests {
         assert_eq!(p0.get_gold(), 0);
         assert_eq!(g.state(), State::ActionPhase);
     }
+    #[test]
+    fn setup_separated_from_play() {
+        let mutations = Game::new_first_game(Players::Two).1;
+        let begin = mutations.iter().position(|m| matches!(m, Mutation::BeginGame)).unwrap();
+        let first_turn = mutations.iter().position(|m| matches!(m, Mutation::ChangeTurn(_))).unwrap();
+        assert!(begin < first_turn);
+        let (setup, play) = mutations.split_at(begin);
+        let is_draw = |m: &&Mutation| matches!(m, Mutation::DrawCard(_, _));
+        assert_eq!(setup.iter().filter(is_draw).count(), 10);
+        assert_eq!(play.iter().filter(is_draw).count(), 0);
+    }
+    #[test]
+    fn gained_cards_tracked_per_turn() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        assert_eq!(g.board_state().get_player(Player::P0).unwrap().gained_iter().count(), 0);
+        assert!(g.apply_mutations(&vec![
+            Mutation::GainCard(Player::P0, Card::Silver),
+            Mutation::GainCard(Player::P0, Card::Village),
+        ]));
+        assert_eq!(g.board_state().get_player(Player::P0).unwrap().gained_iter().collect::<Vec<Card>>(),
+            vec![Card::Silver, Card::Village]);
+        for _ in 0..2 {
+            g.act(Action::EndAction).unwrap();
+            g.act(Action::EndBuy).unwrap();
+        }
+        assert_eq!(g.board_state().active_player(), Player::P0);
+        assert_eq!(g.board_state().get_player(Player::P0).unwrap().gained_iter().count(), 0);
+    }
+    #[test]
+    fn active_player_state_is_p0() {
+        let g = Game::new_first_game(Players::Two).0;
+        assert_eq!(g.active_player_state(), g.board_state().get_player(Player::P0).unwrap());
+        assert_eq!(g.board_state().active_player_state(), g.board_state().get_player(Player::P0));
+    }
+    #[test]
+    fn supply_by_cost() {
+        let g = Game::new_first_game(Players::Two).0;
+        assert_eq!(g.board_state().supply_cards_costing(4, false), vec![
+            Card::Copper, Card::Silver, Card::Estate, Card::Curse, Card::Cellar, Card::Militia,
+            Card::Moat, Card::Remodel, Card::Smithy, Card::Village, Card::Woodcutter, Card::Workshop]);
+        assert_eq!(g.board_state().supply_cards_costing(5, true), vec![Card::Duchy, Card::Market, Card::Mine]);
+        assert_eq!(g.board_state().supply_cards_costing(7, true), vec![]);
+    }
+    #[test]
+    fn gain_from_empty_pile() {
+        let mut g = Game::from_mutations(&vec![
+            Mutation::SetPlayers(Players::Two),
+            Mutation::AddStack(Card::Copper, 10),
+            Mutation::AddStack(Card::Curse, 0),
+        ]).unwrap();
+        let mutations = {
+            let mut up = Update::from(&mut g);
+            assert_eq!(up.gain_card(Player::P0, Card::Curse), Some(()));
+            assert_eq!(up.gain_card(Player::P0, Card::Gold), None);
+            up.apply()
+        };
+        assert!(mutations.is_empty());
+        // Empty piles are never offered as something to gain
+        assert_eq!(g.board_state().supply_cards_costing(0, false), vec![Card::Copper]);
+    }
+    #[test]
+    fn auto_play_treasures_counts_gold() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        assert!(g.auto_play_treasures().is_empty());
+        g.act(Action::EndAction).unwrap();
+        let coppers = g.active_player_state().hand_iter().filter(|c| *c == Some(Card::Copper)).count();
+        let mutations = g.auto_play_treasures();
+        assert_eq!(mutations.len(), coppers * 2);
+        assert_eq!(g.active_player_state().get_gold(), coppers as u32);
+        assert_eq!(g.active_player_state().played_iter().count(), coppers);
+        assert!(g.active_player_state().hand_iter().all(|c| c == Some(Card::Estate)));
+    }
+    #[test]
+    fn treasure_hand_has_no_playable_actions() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Copper, Card::Copper, Card::Silver, Card::Gold, Card::Copper]);
+        assert!(!g.can_play_any_action());
+        assert_eq!(g.valid_actions(), vec![Action::EndAction]);
+        g.state.set_hand(Player::P0, &[Card::Village, Card::Copper]);
+        assert!(g.can_play_any_action());
+        g.state = g.state.clone().mutate(Mutation::SetActions(Player::P0, 0)).unwrap();
+        assert!(!g.can_play_any_action());
+    }
+    #[test]
+    fn draw_from_empty_deck_and_discard() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.stack_deck(Player::P0, &[]);
+        g.state.set_hand(Player::P0, &[Card::Smithy, Card::Copper]);
+        assert_eq!(g.active_player_state().discard_iter().count(), 0);
+        {
+            let mut up = Update::from(&mut g);
+            let before = up.state.clone();
+            assert_eq!(up.try_draw_card(Player::P0), None);
+            assert!(up.updates.is_empty());
+            assert_eq!(up.state, before);
+        }
+        // Drawing is optional, so the Smithy is still played
+        let mutations = g.act(Action::PlayAction(Card::Smithy)).unwrap();
+        assert!(!mutations.iter().any(|m| matches!(m, Mutation::ShuffleDiscard(_) | Mutation::DrawCard(..))));
+        assert_eq!(g.active_player_state().hand_iter().collect::<Vec<Option<Card>>>(), vec![Some(Card::Copper)]);
+        assert_eq!(g.active_player_state().get_shuffle_count(), 1);
+    }
+    #[test]
+    fn play_treasures_for_gold() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Copper, Card::Gold, Card::Silver, Card::Estate, Card::Copper]);
+        assert_eq!(g.act(Action::PlayTreasure(Card::Copper)), Err(ActionError::WrongState(State::ActionPhase)));
+        g.act(Action::EndAction).unwrap();
+        assert_eq!(g.act(Action::PlayTreasure(Card::Estate)), Err(ActionError::CannotPlay(Card::Estate)));
+        g.act(Action::PlayTreasure(Card::Copper)).unwrap();
+        assert_eq!(g.active_player_state().get_gold(), 1);
+        g.act(Action::PlayTreasure(Card::Gold)).unwrap();
+        assert_eq!(g.active_player_state().get_gold(), 4);
+        assert_eq!(g.act(Action::PlayTreasure(Card::Gold)), Err(ActionError::NotInHand(Card::Gold)));
+        let rest = g.act(Action::PlayAllTreasures).unwrap();
+        assert_eq!(rest.len(), 4);
+        assert_eq!(g.active_player_state().get_gold(), 7);
+        assert_eq!(g.active_player_state().hand_iter().collect::<Vec<Option<Card>>>(), vec![Some(Card::Estate)]);
+        assert_eq!(g.active_player_state().played_iter().count(), 4);
+        assert_eq!(g.act(Action::PlayAllTreasures), Ok(Vec::new()));
+    }
+    #[cfg(feature = "scenario-edit")]
+    #[test]
+    fn edit_then_validate() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        {
+            let board = g.board_state_mut();
+            let edited = board.clone().mutate(Mutation::GainCard(Player::P1, Card::Gold)).unwrap();
+            *board = edited;
+        }
+        assert_eq!(g.board_state().validate(), Ok(()));
+        assert_eq!(g.board_state().count_supply(Card::Gold), Some(29));
+    }
+    #[test]
+    fn skip_to_buy() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        assert_eq!(g.skip_to_buy_phase(), Some(vec![Mutation::SetPhase(Player::P0, PlayerPhase::Buy)]));
+        assert_eq!(g.state(), State::BuyPhase);
+        assert_eq!(g.skip_to_buy_phase(), None);
+        assert_eq!(g.state(), State::BuyPhase);
+    }
+    #[test]
+    fn cleanup_draws_next_hand_size() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        assert_eq!(g.active_player_state().get_next_hand_size(), 5);
+        assert!(g.apply_mutations(&vec![Mutation::SetNextHandSize(Player::P0, 7)]));
+        g.act(Action::EndAction).unwrap();
+        g.act(Action::EndBuy).unwrap();
+        assert_eq!(g.board_state().get_player(Player::P0).unwrap().hand_size(), 7);
+        assert_eq!(g.board_state().get_player(Player::P1).unwrap().hand_size(), 5);
+    }
+    #[test]
+    fn open_game_reveals_draws() {
+        let rules = Rules::new(Players::Two, card::lists::FIRST_SET);
+        let (mut g, mutations) = Game::new_open(rules);
+        let mut p0 = Game::from_mutations(&obscure_mutations(mutations.clone(), Player::P0)).unwrap();
+        let opponent = p0.board_state().get_player(Player::P1).unwrap();
+        assert!(opponent.hand_iter().all(|c| c.is_some()));
+        assert_eq!(opponent.known_hand(), g.board_state().get_player(Player::P1).unwrap().known_hand());
+        g.act(Action::EndAction).unwrap();
+        let mutations = g.act(Action::EndBuy).unwrap();
+        assert!(p0.apply_mutations(&obscure_mutations(mutations.clone(), Player::P1)));
+        assert!(p0.board_state().get_player(Player::P0).unwrap().hand_iter().all(|c| c.is_some()));
+        // Without open mode the opponent's hand is hidden
+        let (_, mutations) = Game::new_first_game(Players::Two);
+        let p0 = Game::from_mutations(&obscure_mutations(mutations.clone(), Player::P0)).unwrap();
+        assert!(p0.board_state().get_player(Player::P1).unwrap().hand_iter().all(|c| c.is_none()));
+    }
+    #[test]
+    fn supply_matches_rules_piles() {
+        let rules = Rules::new(Players::Two, card::lists::FIRST_SET);
+        let piles = rules.supply_piles();
+        assert_eq!(piles.len(), 17);
+        let (g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        assert_eq!(g.board_state().supply_stacks().count(), piles.len());
+        let rules = Rules {
+            curse_pile: false,
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        assert!(rules.supply_piles().iter().all(|&(card, _)| card != Card::Curse));
+        let (g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        assert_eq!(g.board_state().supply_stacks().count(), 16);
+        assert_eq!(g.board_state().count_supply(Card::Curse), None);
+    }
+    #[test]
+    fn replay_stops_at_turn_start() {
+        let (mut g, mut log) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        for _ in 0..3 {
+            log.extend(g.act(Action::EndAction).unwrap());
+            log.extend(g.act(Action::EndBuy).unwrap());
+        }
+        let replay = Game::replay_to_turn(&log, 2).unwrap();
+        assert_eq!(replay.board_state().active_player(), Player::P1);
+        assert_eq!(replay.state(), State::ActionPhase);
+        assert_eq!(replay.active_player_state().hand_size(), 5);
+        assert_eq!(Game::replay_to_turn(&log, 4).unwrap().board_state().active_player(), Player::P1);
+        assert!(Game::replay_to_turn(&log, 0).is_none());
+        assert!(Game::replay_to_turn(&log, 5).is_none());
+    }
+    #[test]
+    fn gained_piles_are_touched() {
+        let (g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        assert!(g.board_state().piles_touched().is_empty());
+        let bs = g.board_state().clone().mutate(Mutation::GainCard(Player::P0, Card::Silver)).unwrap();
+        assert_eq!(bs.piles_touched(), vec![Card::Silver]);
+    }
+    #[test]
+    fn viewer_cannot_see_opponent_draws() {
+        let (mut g, mutations) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        let mut p1 = Game::from_mutations(&obscure_mutations(mutations.clone(), Player::P1)).unwrap();
+        let top = g.active_player_state().draw_iter().next().unwrap();
+        assert!(top.is_some());
+        let draw = vec![Mutation::Batch(vec![Mutation::DrawCard(Player::P0, top)])];
+        assert!(g.apply_mutations(&draw));
+        assert!(p1.apply_mutations_as(&draw, Player::P1));
+        assert!(g.active_player_state().hand_iter().all(|c| c.is_some()));
+        assert_eq!(p1.active_player_state().hand_size(), 6);
+        assert!(p1.active_player_state().hand_iter().all(|c| c.is_none()));
+    }
+    #[test]
+    fn opponent_draws_are_obscured() {
+        let drawn = vec![Mutation::DrawCard(Player::P0, Some(Card::Gold)), Mutation::ShuffleDiscard(Player::P1)];
+        assert_eq!(obscure_mutations(drawn.clone(), Player::P0), drawn);
+        assert_eq!(obscure_mutations(drawn.clone(), Player::P1),
+            vec![Mutation::DrawCard(Player::P0, None), Mutation::ShuffleDiscard(Player::P1)]);
+        let batch = Mutation::Batch(drawn);
+        assert_eq!(batch.obscure_for(Player::P1),
+            Mutation::Batch(vec![Mutation::DrawCard(Player::P0, None), Mutation::ShuffleDiscard(Player::P1)]));
+    }
+    #[test]
+    fn reveals_hidden_from_others() {
+        let mut copper = CardSet::empty();
+        copper.insert(Card::Copper, 1);
+        let to_p1 = Reveal::Just(PlayerSet::just(Player::P1));
+        let reveal = Mutation::RevealHandCards(Player::P0, Some(copper), to_p1);
+        assert_eq!(reveal.obscure_for(Player::P0), reveal);
+        assert_eq!(reveal.obscure_for(Player::P1), reveal);
+        assert_eq!(reveal.obscure_for(Player::P2), Mutation::RevealHandCards(Player::P0, None, to_p1));
+        let public = Mutation::RevealHandCards(Player::P0, Some(copper), Reveal::All);
+        assert_eq!(public.obscure_for(Player::P2), public);
+    }
+    #[test]
+    fn card_pool_is_conserved() {
+        let rules = Rules::new(Players::Two, card::lists::FIRST_SET);
+        let pool = rules.card_pool();
+        assert_eq!(pool.total_count(), 270);
+        let (mut g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        g.board_state().assert_conserved(&pool);
+        g.act(Action::EndAction).unwrap();
+        g.act(Action::EndBuy).unwrap();
+        g.board_state().assert_conserved(&pool);
+    }
+    #[test]
+    fn new_with_rng_is_deterministic() {
+        use rand::SeedableRng;
+        let rules = || Rules::new(Players::Two, card::lists::FIRST_SET);
+        let mut a = state::RNGSource::from_seed(DUMMY_SEED);
+        let mut b = state::RNGSource::from_seed(DUMMY_SEED);
+        let (g1, m1) = Game::new_with_rng(rules(), &mut a);
+        let (g2, m2) = Game::new_with_rng(rules(), &mut b);
+        assert_eq!(m1, m2);
+        assert_eq!(g1.board_state(), g2.board_state());
+        // The parent RNG advances, so the next game differs
+        let (_, m3) = Game::new_with_rng(rules(), &mut a);
+        assert_ne!(m1, m3);
+    }
+    #[test]
+    fn supply_cards_in_display_order() {
+        let (g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        assert_eq!(g.board_state().supply_cards(), vec![
+            Card::Copper, Card::Silver, Card::Gold, Card::Estate, Card::Duchy, Card::Province, Card::Curse,
+            Card::Cellar, Card::Moat, Card::Village, Card::Woodcutter, Card::Workshop,
+            Card::Militia, Card::Remodel, Card::Smithy, Card::Market, Card::Mine]);
+    }
+    #[test]
+    fn strict_draws_do_not_shuffle() {
+        let play_turns = |g: &mut Game, turns| for _ in 0..turns {
+            g.act(Action::EndAction).unwrap();
+            g.act(Action::EndBuy).unwrap();
+        };
+        // Both players have drawn their whole deck after their second turn
+        let (mut lenient, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        play_turns(&mut lenient, 3);
+        assert_eq!(lenient.board_state().get_player(Player::P0).unwrap().hand_size(), 5);
+        assert_eq!(lenient.board_state().get_player(Player::P0).unwrap().get_shuffle_count(), 2);
+        let rules = Rules {
+            auto_shuffle: false,
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        let (mut strict, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        play_turns(&mut strict, 3);
+        let p0 = strict.board_state().get_player(Player::P0).unwrap();
+        assert_eq!(p0.hand_size(), 0);
+        assert_eq!(p0.get_shuffle_count(), 1);
+        assert!(Update::from(&mut strict).try_draw_card(Player::P0).is_none());
+        assert!(Update::from(&mut lenient).try_draw_card(Player::P0).is_some());
+    }
+    #[test]
+    fn apply_reports_failing_mutation() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        // The full game knows the top card of the starting deck is not a Gold
+        let stream = vec![Mutation::SetGold(Player::P0, 1), Mutation::DrawCard(Player::P0, Some(Card::Gold))];
+        let err = g.try_apply_mutations(&stream).unwrap_err();
+        assert_eq!(err, ApplyError { index: 1, mutation: Mutation::DrawCard(Player::P0, Some(Card::Gold)) });
+        assert_eq!(err.to_string(), "Mutation 1 (DrawCard(P0, Some(Gold))) is not valid for the board");
+        assert_eq!(g.active_player_state().get_gold(), 0);
+    }
+    #[test]
+    fn playable_actions_need_actions() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        assert!(g.playable_actions_in_hand().is_empty());
+        g.state.set_hand(Player::P0, &[Card::Smithy, Card::Copper, Card::Village, Card::Smithy]);
+        assert_eq!(g.playable_actions_in_hand(), vec![Card::Smithy, Card::Village]);
+        g.state = g.state.clone().mutate(Mutation::SetActions(Player::P0, 0)).unwrap();
+        assert!(g.playable_actions_in_hand().is_empty());
+    }
+    #[test]
+    fn reveals_update_perspectives() {
+        let (g, mutations) = Game::new_from_seed(Rules::new(Players::Three, card::lists::FIRST_SET), DUMMY_SEED);
+        let mut p1 = Game::from_mutations(&obscure_mutations(mutations.clone(), Player::P1)).unwrap();
+        let mut p2 = Game::from_mutations(&obscure_mutations(mutations.clone(), Player::P2)).unwrap();
+        let hand = g.active_player_state().known_hand();
+        // Only P1 is shown the hand
+        let directed = vec![Mutation::RevealHandCards(Player::P0, Some(hand), Reveal::Just(PlayerSet::just(Player::P1)))];
+        assert!(p1.apply_mutations_as(&directed, Player::P1));
+        assert!(p2.apply_mutations_as(&directed, Player::P2));
+        assert_eq!(p1.active_player_state().known_hand(), hand);
+        assert!(p2.active_player_state().hand_iter().all(|c| c.is_none()));
+        // Everyone sees a public reveal
+        let public = vec![Mutation::RevealHandCards(Player::P0, Some(hand), Reveal::All)];
+        assert!(p2.apply_mutations_as(&public, Player::P2));
+        assert_eq!(p2.active_player_state().known_hand(), hand);
+        assert_eq!(p2.active_player_state().hand_size(), 5);
+    }
+    #[test]
+    fn buy_needs_gold_buys_and_supply() {
+        let rules = Rules {
+            victory_pile_sizes: Some(enum_map! {
+                Card::Estate => 8,
+                Card::Duchy => 0,
+                _ => 8,
+            }),
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        let (mut g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        // Cannot buy during the action phase
+        assert_eq!(g.act(Action::Buy(Card::Copper)), Err(ActionError::WrongState(State::ActionPhase)));
+        g.act(Action::EndAction).unwrap();
+        assert!(g.apply_mutations(&vec![Mutation::SetGold(Player::P0, 2), Mutation::SetBuys(Player::P0, 2)]));
+        assert_eq!(g.act(Action::Buy(Card::Silver)), Err(ActionError::NotEnoughGold { cost: 3, gold: 2 }));
+        assert!(g.apply_mutations(&vec![Mutation::SetGold(Player::P0, 8)]));
+        assert_eq!(g.act(Action::Buy(Card::Duchy)), Err(ActionError::PileEmpty(Card::Duchy)));
+        let silvers = g.board_state().count_supply(Card::Silver).unwrap();
+        g.act(Action::Buy(Card::Silver)).unwrap();
+        assert_eq!(g.board_state().count_supply(Card::Silver), Some(silvers - 1));
+        assert_eq!(g.active_player_state().gained_iter().collect::<Vec<Card>>(), vec![Card::Silver]);
+        assert_eq!(g.active_player_state().get_gold(), 5);
+        assert_eq!(g.active_player_state().get_buys(), 1);
+        g.act(Action::Buy(Card::Silver)).unwrap();
+        // Out of buys
+        assert_eq!(g.act(Action::Buy(Card::Copper)), Err(ActionError::NoBuys));
+    }
+    #[test]
+    fn act_reports_wrong_state() {
+        let rules = Rules { curse_pile: false, ..Rules::new(Players::Two, card::lists::FIRST_SET) };
+        let (mut g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        assert_eq!(g.act(Action::EndBuy), Err(ActionError::WrongState(State::ActionPhase)));
+        assert_eq!(g.act(Action::PlayAction(Card::Copper)), Err(ActionError::CannotPlay(Card::Copper)));
+        g.act(Action::EndAction).unwrap();
+        assert_eq!(g.act(Action::EndAction), Err(ActionError::WrongState(State::BuyPhase)));
+        assert_eq!(g.act(Action::PlayAction(Card::Smithy)), Err(ActionError::WrongState(State::BuyPhase)));
+        assert_eq!(g.act(Action::Buy(Card::Curse)), Err(ActionError::NotInSupply(Card::Curse)));
+        g.act(Action::Concede).unwrap();
+        assert_eq!(g.act(Action::Concede), Err(ActionError::WrongState(State::GameOver)));
+    }
+    #[test]
+    fn play_smithy() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        // Cannot play a card that is not in hand
+        assert_eq!(g.act(Action::PlayAction(Card::Smithy)), Err(ActionError::NotInHand(Card::Smithy)));
+        g.state.set_hand(Player::P0, &[Card::Smithy, Card::Smithy, Card::Copper, Card::Copper, Card::Estate]);
+        g.act(Action::PlayAction(Card::Smithy)).unwrap();
+        let p0 = g.active_player_state();
+        assert_eq!(p0.hand_size(), 4 + 3);
+        assert_eq!(p0.get_actions(), 0);
+        assert_eq!(p0.played_iter().collect::<Vec<Card>>(), vec![Card::Smithy]);
+        // No actions left for the second Smithy
+        assert_eq!(g.act(Action::PlayAction(Card::Smithy)), Err(ActionError::NoActions));
+    }
+    #[test]
+    fn play_village() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Village, Card::Village, Card::Copper, Card::Copper, Card::Estate]);
+        g.act(Action::PlayAction(Card::Village)).unwrap();
+        assert_eq!(g.active_player_state().hand_size(), 5);
+        assert_eq!(g.active_player_state().get_actions(), 2);
+        // With nothing left to draw the actions are still given
+        g.state.stack_deck(Player::P0, &[]);
+        g.act(Action::PlayAction(Card::Village)).unwrap();
+        assert_eq!(g.active_player_state().hand_size(), 4);
+        assert_eq!(g.active_player_state().get_actions(), 3);
+    }
+    #[test]
+    fn play_festival() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Festival, Card::Copper, Card::Copper, Card::Estate, Card::Estate]);
+        let deck = g.active_player_state().draw_iter().count();
+        g.act(Action::PlayAction(Card::Festival)).unwrap();
+        let p0 = g.active_player_state();
+        assert_eq!((p0.get_actions(), p0.get_buys(), p0.get_gold()), (2, 2, 2));
+        // Nothing is drawn
+        assert_eq!(p0.hand_size(), 4);
+        assert_eq!(p0.draw_iter().count(), deck);
+        g.act(Action::EndAction).unwrap();
+        assert_eq!(g.active_player_state().get_buys(), 2);
+        assert_eq!(g.active_player_state().get_gold(), 2);
+    }
+    #[test]
+    fn play_laboratory() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Laboratory, Card::Laboratory, Card::Copper, Card::Copper, Card::Estate]);
+        g.act(Action::PlayAction(Card::Laboratory)).unwrap();
+        assert_eq!(g.active_player_state().hand_size(), 6);
+        assert_eq!(g.active_player_state().get_actions(), 1);
+        // The action is given back, so another can be played straight away
+        g.act(Action::PlayAction(Card::Laboratory)).unwrap();
+        assert_eq!(g.active_player_state().hand_size(), 7);
+        assert_eq!(g.active_player_state().get_actions(), 1);
+    }
+    #[test]
+    fn play_woodcutter() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Woodcutter, Card::Copper, Card::Copper, Card::Estate, Card::Estate]);
+        g.act(Action::PlayAction(Card::Woodcutter)).unwrap();
+        assert_eq!(g.active_player_state().get_buys(), 2);
+        assert_eq!(g.active_player_state().get_gold(), 2);
+        assert_eq!(g.active_player_state().get_actions(), 0);
+        // The buy and coins carry into the buy phase
+        g.act(Action::EndAction).unwrap();
+        assert_eq!(g.active_player_state().get_buys(), 2);
+        assert_eq!(g.active_player_state().get_gold(), 2);
+        assert_eq!(g.auto_play_treasures().len(), 4);
+        assert_eq!(g.active_player_state().get_gold(), 4);
+    }
+    #[test]
+    fn play_market() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Market, Card::Copper, Card::Copper, Card::Estate, Card::Estate]);
+        let mutations = g.act(Action::PlayAction(Card::Market)).unwrap();
+        let p0 = g.active_player_state();
+        assert_eq!(p0.hand_size(), 5);
+        assert_eq!(p0.get_actions(), 1);
+        assert_eq!(p0.get_buys(), 2);
+        assert_eq!(p0.get_gold(), 1);
+        assert!(mutations.contains(&Mutation::SetBuys(Player::P0, 2)));
+        assert!(mutations.contains(&Mutation::SetGold(Player::P0, 1)));
+    }
+    #[test]
+    fn play_moat() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Moat, Card::Copper, Card::Copper, Card::Estate, Card::Estate]);
+        g.act(Action::PlayAction(Card::Moat)).unwrap();
+        assert_eq!(g.active_player_state().hand_size(), 6);
+        assert_eq!(g.active_player_state().get_actions(), 0);
+        // Playing a Moat does not protect, only revealing it does
+        assert!(!g.active_player_state().is_protected());
+        g.state = g.state.clone().mutate(Mutation::SetProtected(Player::P1, true)).unwrap();
+        assert!(g.board_state().get_player(Player::P1).unwrap().is_protected());
+        g.act(Action::EndAction).unwrap();
+        g.act(Action::EndBuy).unwrap();
+        assert!(!g.active_player_state().is_protected());
+    }
+    #[test]
+    fn play_cellar() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Cellar, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Cellar)).unwrap();
+        let decision = Decision::CellarDiscard { player: Player::P0, max: 4 };
+        assert_eq!(g.pending_decision(), Some(decision));
+        assert_eq!(g.state(), State::PendingDecision);
+        assert_eq!(g.active_player_state().get_actions(), 1);
+        // Nothing else can happen until the decision is resolved
+        assert!(g.act(Action::EndAction).is_err());
+        // Cannot discard cards that are not in hand
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Estate, Card::Gold])).is_none());
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Estate; 3])).is_none());
+        assert_eq!(g.pending_decision(), Some(decision));
+        g.resolve_decision(decision, Choice::Cards(vec![Card::Estate, Card::Estate])).unwrap();
+        assert_eq!(g.pending_decision(), None);
+        assert_eq!(g.state(), State::ActionPhase);
+        let p0 = g.active_player_state();
+        assert_eq!(p0.hand_size(), 4);
+        assert_eq!(p0.discard_iter().collect::<Vec<Card>>(), vec![Card::Estate, Card::Estate]);
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![])).is_none());
+    }
+    #[test]
+    fn play_chapel() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Chapel, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Chapel)).unwrap();
+        let decision = Decision::TrashUpTo { player: Player::P0, max: 4 };
+        assert_eq!(g.pending_decision(), Some(decision));
+        assert_eq!(decision.player(), Player::P0);
+        // Every trashed card must be in hand, and nothing is trashed if any is not
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Estate, Card::Gold])).is_none());
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Estate; 3])).is_none());
+        assert_eq!(g.board_state().count_trash(Card::Estate), 0);
+        g.resolve_decision(decision, Choice::Cards(vec![Card::Estate, Card::Estate])).unwrap();
+        assert_eq!(g.pending_decision(), None);
+        assert_eq!(g.board_state().count_trash(Card::Estate), 2);
+        assert_eq!(g.active_player_state().known_hand(), vec![(Card::Copper, 2)].into_iter().collect());
+    }
+    #[test]
+    fn remodel_with_nothing_to_gain() {
+        let state = BoardState::builder()
+            .players(Players::Two)
+            .supply(Card::Copper, 0)
+            .supply(Card::Estate, 0)
+            .supply(Card::Smithy, 10)
+            .player_hand(Player::P0, Card::Remodel, 1)
+            .player_hand(Player::P0, Card::Copper, 1)
+            .build()
+            .unwrap()
+            .mutate(Mutation::SetPhase(Player::P0, PlayerPhase::Action)).unwrap()
+            .mutate(Mutation::SetActions(Player::P0, 1)).unwrap();
+        let mut g = Game::from_state(state).unwrap();
+        g.act(Action::PlayAction(Card::Remodel)).unwrap();
+        // The Copper is the only card to trash and nothing can be gained, so neither is asked
+        assert_eq!(g.pending_decision(), None);
+        assert_eq!(g.board_state().count_trash(Card::Copper), 1);
+        assert_eq!(g.state(), State::ActionPhase);
+        // Gaining nothing is the only answer, so agents are not stuck either
+        let gain = Decision::Gain { player: Player::P0, max_cost: 2 };
+        assert_eq!(gain.is_forced(&g), Some(Choice::None));
+        assert_eq!(agent::simple_resolution(&g, gain), Choice::None);
+    }
+    #[test]
+    fn play_workshop() {
+        let rules = Rules {
+            victory_pile_sizes: Some(enum_map! {
+                // No Estates left once the starting decks are dealt
+                Card::Estate => 0,
+                _ => 8,
+            }),
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        let (mut g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Workshop, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Workshop)).unwrap();
+        let decision = Decision::Gain { player: Player::P0, max_cost: 4 };
+        assert_eq!(g.pending_decision(), Some(decision));
+        assert!(g.resolve_decision(decision, Choice::Card(Card::Gold)).is_none());
+        assert!(g.resolve_decision(decision, Choice::None).is_none());
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Silver, Card::Silver])).is_none());
+        assert_eq!(g.board_state().count_supply(Card::Estate), Some(0));
+        assert!(g.resolve_decision(decision, Choice::Card(Card::Estate)).is_none());
+        g.resolve_decision(decision, Choice::Card(Card::Silver)).unwrap();
+        assert_eq!(g.active_player_state().discard_iter().collect::<Vec<Card>>(), vec![Card::Silver]);
+        assert_eq!(g.active_player_state().gained_iter().collect::<Vec<Card>>(), vec![Card::Silver]);
+        assert_eq!(g.pending_decision(), None);
+    }
+    #[test]
+    fn play_militia() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Four, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Militia, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.state.set_hand(Player::P3, &[Card::Moat, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Militia)).unwrap();
+        assert_eq!(g.active_player_state().get_gold(), 2);
+        // Only P3 has a Moat to react with, and revealing it protects them
+        let react = Decision::ReactToAttack { player: Player::P3, attack: Card::Militia };
+        assert_eq!(g.pending_decision(), Some(react));
+        assert!(g.resolve_decision(react, Choice::Cards(vec![Card::Moat])).is_none());
+        let mutations = g.resolve_decision(react, Choice::Bool(true)).unwrap();
+        assert!(mutations.contains(&Mutation::RevealHandCards(Player::P3, Some(Some(Card::Moat).into_iter().collect()), Reveal::All)));
+        assert!(g.board_state().get_player(Player::P3).unwrap().is_protected());
+        // Opponents discard in turn order, skipping the protected P3
+        for player in &[Player::P1, Player::P2] {
+            let decision = Decision::MilitiaDiscard { player: *player, to: 3 };
+            assert_eq!(g.pending_decision(), Some(decision));
+            assert_eq!(decision.player(), *player);
+            let hand = g.board_state().get_player(*player).unwrap().hand_iter()
+                .map(|c| c.unwrap())
+                .collect::<Vec<Card>>();
+            assert!(g.resolve_decision(decision, Choice::Cards(hand[..1].to_vec())).is_none());
+            assert!(g.resolve_decision(decision, Choice::Cards(hand[..3].to_vec())).is_none());
+            g.resolve_decision(decision, Choice::Cards(hand[..2].to_vec())).unwrap();
+            assert_eq!(g.board_state().get_player(*player).unwrap().hand_size(), 3);
+        }
+        assert_eq!(g.pending_decision(), None);
+        assert_eq!(g.board_state().get_player(Player::P3).unwrap().hand_size(), 5);
+    }
+    #[test]
+    fn militia_queues_every_discard() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Four, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Militia, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        assert!(g.pending_decisions().is_empty());
+        g.act(Action::PlayAction(Card::Militia)).unwrap();
+        assert_eq!(g.pending_decisions(), &[
+            Decision::MilitiaDiscard { player: Player::P1, to: 3 },
+            Decision::MilitiaDiscard { player: Player::P2, to: 3 },
+            Decision::MilitiaDiscard { player: Player::P3, to: 3 },
+        ]);
+        assert_eq!(g.pending_decision(), g.pending_decisions().first().cloned());
+    }
+    #[test]
+    fn militia_attacks_in_turn_order() {
+        let rules = Rules { starting_player: Player::P2, ..Rules::new(Players::Four, card::lists::FIRST_SET) };
+        let (mut g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        g.state.set_hand(Player::P2, &[Card::Militia, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Militia)).unwrap();
+        // Opponents are attacked clockwise from the attacker
+        for player in &[Player::P3, Player::P0, Player::P1] {
+            let decision = Decision::MilitiaDiscard { player: *player, to: 3 };
+            assert_eq!(g.pending_decision(), Some(decision));
+            let hand = g.board_state().get_player(*player).unwrap().known_hand().into_iter().collect::<Vec<Card>>();
+            g.resolve_decision(decision, Choice::Cards(hand[..2].to_vec())).unwrap();
+        }
+        assert_eq!(g.pending_decision(), None);
+    }
+    #[test]
+    fn moat_reactions_in_turn_order() {
+        let rules = Rules { starting_player: Player::P2, ..Rules::new(Players::Four, card::lists::FIRST_SET) };
+        let (mut g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        g.state.set_hand(Player::P2, &[Card::Militia, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.state.set_hand(Player::P0, &[Card::Moat, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.state.set_hand(Player::P3, &[Card::Moat, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Militia)).unwrap();
+        for player in &[Player::P3, Player::P0] {
+            let react = Decision::ReactToAttack { player: *player, attack: Card::Militia };
+            assert_eq!(g.pending_decision(), Some(react));
+            g.resolve_decision(react, Choice::Bool(false)).unwrap();
+        }
+        // Having both declined, the attack then resolves in the same order
+        assert_eq!(g.pending_decision(), Some(Decision::MilitiaDiscard { player: Player::P3, to: 3 }));
+    }
+    #[test]
+    fn forced_decisions_resolve_themselves() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Militia, Card::Cellar, Card::Village]);
+        g.state.set_hand(Player::P1, &[Card::Copper, Card::Estate]);
+        let discard = Decision::MilitiaDiscard { player: Player::P1, to: 3 };
+        assert_eq!(discard.is_forced(&g), Some(Choice::Cards(vec![])));
+        assert_eq!(Decision::CellarDiscard { player: Player::P0, max: 2 }.is_forced(&g), None);
+        // Already at two cards, P1 discards nothing without being asked
+        let mutations = g.act(Action::PlayAction(Card::Militia)).unwrap();
+        assert_eq!(g.pending_decision(), None);
+        assert!(!mutations.iter().any(|m| matches!(m, Mutation::DiscardHand(Player::P1, _))));
+        assert_eq!(g.board_state().get_player(Player::P1).unwrap().hand_size(), 2);
+        // A Cellar with an empty hand has nothing to choose
+        g.state.set_hand(Player::P0, &[Card::Cellar]);
+        g.state = g.state.clone().mutate(Mutation::SetActions(Player::P0, 1)).unwrap();
+        g.act(Action::PlayAction(Card::Cellar)).unwrap();
+        assert_eq!(g.pending_decision(), None);
+        assert_eq!(g.state(), State::ActionPhase);
+    }
+    #[test]
+    fn militia_after_declined_moat() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Militia, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.state.set_hand(Player::P1, &[Card::Moat, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Militia)).unwrap();
+        let react = Decision::ReactToAttack { player: Player::P1, attack: Card::Militia };
+        assert_eq!(g.pending_decision(), Some(react));
+        // Not revealing the Moat leaves P1 open to the attack
+        g.resolve_decision(react, Choice::Bool(false)).unwrap();
+        assert!(!g.board_state().get_player(Player::P1).unwrap().is_protected());
+        assert_eq!(g.pending_decision(), Some(Decision::MilitiaDiscard { player: Player::P1, to: 3 }));
+    }
+    #[test]
+    fn choices_must_match_decision() {
+        let play = |card: Card| {
+            let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+            g.state.set_hand(Player::P0, &[card, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+            g.state.set_hand(Player::P1, &[Card::Moat, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+            g.act(Action::PlayAction(card)).unwrap();
+            g
+        };
+        let cases = vec![
+            (Card::Cellar, Choice::Cards(vec![Card::Estate]), vec![Choice::Card(Card::Estate), Choice::None]),
+            (Card::Chapel, Choice::Cards(vec![Card::Estate]), vec![Choice::Card(Card::Estate), Choice::Bool(true)]),
+            (Card::Workshop, Choice::Card(Card::Silver), vec![Choice::Cards(vec![Card::Silver]), Choice::None]),
+            (Card::Mine, Choice::None, vec![Choice::Card(Card::Copper), Choice::Bool(false)]),
+            (Card::Remodel, Choice::Card(Card::Estate), vec![Choice::Cards(vec![Card::Estate]), Choice::None]),
+            (Card::Militia, Choice::Bool(false), vec![Choice::Cards(vec![]), Choice::None]),
+        ];
+        for (card, right, wrong) in cases {
+            let mut g = play(card);
+            let decision = g.pending_decision().unwrap();
+            for choice in wrong {
+                assert!(g.resolve_decision(decision, choice.clone()).is_none(), "{:?} answered with {:?}", decision, choice);
+            }
+            g.resolve_decision(decision, right).unwrap();
+            if card == Card::Militia {
+                let discard = Decision::MilitiaDiscard { player: Player::P1, to: 3 };
+                assert!(g.resolve_decision(discard, Choice::Card(Card::Estate)).is_none());
+                g.resolve_decision(discard, Choice::Cards(vec![Card::Estate, Card::Estate])).unwrap();
+            }
+        }
+    }
+    #[test]
+    fn play_mine() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Mine, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Mine)).unwrap();
+        let decision = Decision::MineUpgrade { player: Player::P0 };
+        assert_eq!(g.pending_decision(), Some(decision));
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Copper, Card::Gold])).is_none());
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Estate, Card::Silver])).is_none());
+        assert!(g.resolve_decision(decision, Choice::Cards(vec![Card::Silver, Card::Gold])).is_none());
+        g.resolve_decision(decision, Choice::Cards(vec![Card::Copper, Card::Silver])).unwrap();
+        let p0 = g.active_player_state();
+        assert_eq!(p0.known_hand().count(Card::Silver), 1);
+        assert_eq!(p0.known_hand().count(Card::Copper), 1);
+        assert_eq!(g.board_state().trash_iter().collect::<Vec<Card>>(), vec![Card::Copper]);
+        assert_eq!(g.pending_decision(), None);
+    }
+    #[test]
+    fn play_remodel() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Remodel, Card::Estate, Card::Estate, Card::Copper, Card::Copper]);
+        g.act(Action::PlayAction(Card::Remodel)).unwrap();
+        let decision = Decision::RemodelTrash { player: Player::P0 };
+        assert_eq!(g.pending_decision(), Some(decision));
+        assert!(g.resolve_decision(decision, Choice::Card(Card::Gold)).is_none());
+        assert!(g.resolve_decision(decision, Choice::None).is_none());
+        g.resolve_decision(decision, Choice::Card(Card::Estate)).unwrap();
+        assert_eq!(g.board_state().trash_iter().collect::<Vec<Card>>(), vec![Card::Estate]);
+        // Gaining is limited by the cost of the trashed Estate
+        let decision = Decision::Gain { player: Player::P0, max_cost: 4 };
+        assert_eq!(g.pending_decision(), Some(decision));
+        assert!(g.resolve_decision(decision, Choice::Card(Card::Duchy)).is_none());
+        g.resolve_decision(decision, Choice::Card(Card::Smithy)).unwrap();
+        assert_eq!(g.active_player_state().discard_iter().collect::<Vec<Card>>(), vec![Card::Smithy]);
+        assert_eq!(g.pending_decision(), None);
+        // Remodel on its own has nothing to trash
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.state.set_hand(Player::P0, &[Card::Remodel]);
+        g.act(Action::PlayAction(Card::Remodel)).unwrap();
+        assert_eq!(g.pending_decision(), None);
+        assert_eq!(g.state(), State::ActionPhase);
+    }
+    #[test]
+    fn buying_last_province_ends_game() {
+        let rules = Rules {
+            victory_pile_sizes: Some(enum_map! {
+                Card::Estate => 8,
+                Card::Province => 1,
+                _ => 8,
+            }),
+            ..Rules::new(Players::Two, card::lists::FIRST_SET)
+        };
+        let (mut g, _) = Game::new_from_seed(rules, DUMMY_SEED);
+        g.act(Action::EndAction).unwrap();
+        assert!(g.apply_mutations(&vec![Mutation::SetGold(Player::P0, 8), Mutation::SetBuys(Player::P0, 2)]));
+        g.act(Action::Buy(Card::Province)).unwrap();
+        assert_eq!(g.state(), State::GameOver);
+        assert_eq!(g.result(), Some(GameResult { winners: vec![Player::P0], losers: vec![Player::P1] }));
+        assert!(g.act(Action::EndBuy).is_err());
+        assert!(g.act(Action::Buy(Card::Copper)).is_err());
+        assert!(g.act(Action::Concede).is_err());
+    }
+    #[test]
+    fn turn_number_counts_all_players() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Three, card::lists::FIRST_SET), DUMMY_SEED);
+        assert_eq!(g.board_state().turn_number(), 1);
+        for turn in 2..8 {
+            g.act(Action::EndAction).unwrap();
+            g.act(Action::EndBuy).unwrap();
+            assert_eq!(g.board_state().turn_number(), turn);
+        }
+        assert_eq!(g.board_state().active_player(), Player::P0);
+        assert_eq!(g.active_player_state().get_turns_taken(), 3);
+        assert_eq!(BoardState::new(None).turn_number(), 0);
+    }
+    #[test]
+    fn valid_actions_follow_phase() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        // Opening hands only have treasures and Estates
+        assert_eq!(g.valid_actions(), vec![Action::EndAction]);
+        g.state.set_hand(Player::P0, &[Card::Smithy, Card::Village, Card::Copper, Card::Copper, Card::Estate]);
+        assert_eq!(g.valid_actions(), vec![Action::EndAction, Action::PlayAction(Card::Smithy), Action::PlayAction(Card::Village)]);
+        g.act(Action::EndAction).unwrap();
+        g.state = g.state.clone().mutate(Mutation::SetGold(Player::P0, 2)).unwrap();
+        assert_eq!(g.valid_actions(), vec![Action::EndBuy, Action::PlayTreasure(Card::Copper), Action::Buy(Card::Copper),
+            Action::Buy(Card::Estate), Action::Buy(Card::Curse), Action::Buy(Card::Cellar), Action::Buy(Card::Moat)]);
+        for action in g.valid_actions() {
+            assert!(g.clone().act(action).is_ok());
+        }
+        g.state = g.state.clone().mutate(Mutation::SetBuys(Player::P0, 0)).unwrap();
+        assert_eq!(g.valid_actions(), vec![Action::EndBuy, Action::PlayTreasure(Card::Copper)]);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn mutations_round_trip_json() {
+        let (mut g, mut mutations) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        mutations.extend(g.act(Action::EndAction).unwrap());
+        let mut hand = CardSet::empty();
+        hand.insert(Card::Copper, 2);
+        mutations.push(Mutation::Batch(vec![
+            Mutation::RevealHandCards(Player::P1, Some(hand), Reveal::Just(PlayerSet::just(Player::P0))),
+            Mutation::RevealHandCards(Player::P1, None, Reveal::All),
+            Mutation::DrawCard(Player::P1, None),
+        ]));
+        let json = serde_json::to_string(&mutations).unwrap();
+        assert!(json.contains(r#"{"Copper":2}"#));
+        assert_eq!(serde_json::from_str::<Mutations>(&json).unwrap(), mutations);
+    }
+    #[test]
+    fn validate_log_reports_failure() {
+        let (_, mut mutations) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        assert_eq!(Game::validate_log(&mutations), Ok(()));
+        let stack = mutations.iter().position(|m| matches!(m, Mutation::AddStack(..))).unwrap();
+        let duplicate = mutations[stack].clone();
+        mutations.insert(stack + 1, duplicate.clone());
+        assert_eq!(Game::validate_log(&mutations), Err(ApplyError { index: stack + 1, mutation: duplicate }));
+    }
+    #[test]
+    fn seed_reproduces_shuffles() {
+        let (g, mutations) = Game::new_first_game(Players::Two);
+        let seed = g.board_state().seed().unwrap();
+        let (copy, copy_mutations) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), seed);
+        assert_eq!(copy.board_state(), g.board_state());
+        assert_eq!(copy_mutations, mutations);
+        // Perspectives built from the log never know the seed
+        assert_eq!(Game::from_mutations(&mutations).unwrap().board_state().seed(), None);
+    }
+    #[test]
+    fn undo_reverts_actions() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        assert_eq!(g.undo_last(), None);
+        let start = g.clone();
+        let ended = g.act(Action::EndAction).unwrap();
+        assert_eq!(g.state(), State::BuyPhase);
+        assert_eq!(g.undo_last(), Some(ended));
+        assert_eq!(g.state(), State::ActionPhase);
+        assert_eq!(g.board_state(), start.board_state());
+        assert_eq!(g.hash_key(), start.hash_key());
+        // Shuffles replay identically when undoing across turns
+        g.act(Action::EndAction).unwrap();
+        g.auto_play_treasures();
+        g.act(Action::EndBuy).unwrap();
+        let next_turn = g.clone();
+        g.act(Action::EndAction).unwrap();
+        g.undo_last().unwrap();
+        assert_eq!(g.board_state(), next_turn.board_state());
+        while g.undo_last().is_some() {}
+        assert_eq!(g.board_state(), start.board_state());
+    }
+    #[test]
+    fn clones_share_history() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        g.act(Action::EndAction).unwrap();
+        let mut copy = g.clone();
+        assert!(Arc::ptr_eq(g.history.as_ref().unwrap(), copy.history.as_ref().unwrap()));
+        // Each can go its own way and undo without disturbing the other
+        copy.auto_play_treasures();
+        copy.undo_last().unwrap();
+        copy.undo_last().unwrap();
+        assert_eq!(copy.state(), State::ActionPhase);
+        assert_eq!(g.state(), State::BuyPhase);
+        g.undo_last().unwrap();
+        assert_eq!(g.board_state(), copy.board_state());
+    }
+    #[test]
+    fn games_are_send() {
+        // Games are moved between threads, such as when simulating in parallel
+        fn assert_send<T: Send>() {}
+        assert_send::<Game>();
+    }
+    #[test]
+    fn six_player_game() {
+        let (mut g, _) = Game::new_first_game(Players::Six);
+        let supply = g.board_state();
+        assert_eq!(supply.count_supply(Card::Province), Some(18));
+        assert_eq!(supply.count_supply(Card::Duchy), Some(12));
+        assert_eq!(supply.count_supply(Card::Estate), Some(12));
+        assert_eq!(supply.count_supply(Card::Curse), Some(50));
+        assert_eq!(supply.count_supply(Card::Copper), Some(60 - 6 * 7));
+        for player in Player::iter_players(Players::Six) {
+            assert_eq!(supply.get_player(*player).unwrap().hand_size(), 5);
+        }
+        for _ in 0..6 {
+            g.act(Action::EndAction).unwrap();
+            g.act(Action::EndBuy).unwrap();
+        }
+        assert_eq!(g.board_state().active_player(), Player::P0);
+    }
+    #[test]
+    fn failed_append_leaves_update_unchanged() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        let mut hand = CardSet::empty();
+        hand.insert(Card::Gold, 1);
+        let mut up = Update::from(&mut g);
+        up.try_append(Mutation::SetGold(Player::P0, 3)).unwrap();
+        let before = up.state.clone();
+        let top = up.state.get_player(Player::P0).unwrap().draw_iter().next().unwrap().unwrap();
+        let wrong = if top == Card::Copper { Card::Estate } else { Card::Copper };
+        for mutation in [
+            Mutation::DrawCard(Player::P0, Some(wrong)),
+            Mutation::GainCard(Player::P2, Card::Silver),
+            Mutation::RevealHandCards(Player::P0, Some(hand), Reveal::All),
+            Mutation::DiscardHand(Player::P0, Card::Gold),
+            Mutation::Batch(vec![Mutation::SetGold(Player::P0, 5), Mutation::PlayCard(Player::P0, Card::Gold)]),
+        ] {
+            assert_eq!(up.try_append(mutation), None);
+            assert_eq!(up.state, before);
+            assert_eq!(up.state.get_player(Player::P0).unwrap().draw_iter().next(), Some(Some(top)));
+            assert_eq!(up.state.get_player(Player::P0).unwrap().get_gold(), 3);
+        }
+        assert_eq!(up.updates, vec![Mutation::SetGold(Player::P0, 3)]);
+    }
+    #[test]
+    fn big_money_plays_to_the_end() {
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        let mut agents: Vec<Box<dyn agent::Agent>> = vec![Box::new(agent::BigMoney), Box::new(agent::BigMoney)];
+        let result = g.play_out(&mut agents).unwrap();
+        assert_eq!(g.state(), State::GameOver);
+        assert_eq!(Some(result.clone()), g.result());
+        assert_eq!(g.board_state().count_supply(Card::Province), Some(0));
+        assert!(g.board_state().victory_points(result.winners[0]) > 3);
+    }
+    #[test]
+    fn random_games_finish_legally() {
+        for seed in 0..20u8 {
+            let rules = Rules::new(Players::Three, card::lists::FIRST_SET);
+            let (mut g, _) = Game::new_from_seed(rules, [seed; 32]);
+            let mut agents: Vec<Box<dyn agent::Agent>> = (0..3u8)
+                .map(|p| Box::new(agent::RandomAgent::new([seed.wrapping_mul(3).wrapping_add(p); 32])) as Box<dyn agent::Agent>)
+                .collect();
+            let board = g.simulate(&mut agents, 1000).unwrap();
+            assert_eq!(g.state(), State::GameOver);
+            assert_eq!(board.validate(), Ok(()));
+        }
+        let (mut g, _) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        let mut agents: Vec<Box<dyn agent::Agent>> = vec![Box::new(agent::RandomAgent::new(DUMMY_SEED)), Box::new(agent::BigMoney)];
+        assert_eq!(g.simulate(&mut agents, 2), Err(SimulateError::TurnLimit(2)));
+    }
+    #[test]
+    fn opening_full_deck() {
+        let (mut g, mutations) = Game::new_from_seed(Rules::new(Players::Two, card::lists::FIRST_SET), DUMMY_SEED);
+        let opening: CardSet = vec![(Card::Copper, 7), (Card::Estate, 3)].into_iter().collect();
+        assert_eq!(g.board_state().full_deck(Player::P0), Some((opening, 0)));
+        // Playing treasures and ending the turn moves cards around without changing the deck
+        g.act(Action::EndAction).unwrap();
+        g.act(Action::PlayAllTreasures).unwrap();
+        g.act(Action::EndBuy).unwrap();
+        assert_eq!(g.board_state().full_deck(Player::P0), Some((opening, 0)));
+        // A perspective only knows the cards it has seen, which is just its own hand
+        let p1 = Game::from_mutations(&obscure_mutations(mutations, Player::P1)).unwrap();
+        assert_eq!(p1.board_state().full_deck(Player::P0).map(|(known, unknown)| (known.total_count(), unknown)), Some((0, 10)));
+        let (known, unknown) = p1.board_state().full_deck(Player::P1).unwrap();
+        assert_eq!(known, p1.board_state().get_player(Player::P1).unwrap().known_hand());
+        assert_eq!(unknown, 5);
+        assert_eq!(g.board_state().full_deck(Player::P2), None);
+    }
+    #[test]
+    fn affordable_with_four_coins() {
+        let g = Game::new_first_game(Players::Two).0;
+        let affordable: Vec<Card> = g.board_state().affordable(4).collect();
+        assert_eq!(affordable, vec![
+            Card::Copper, Card::Silver, Card::Estate, Card::Curse, Card::Cellar, Card::Militia,
+            Card::Moat, Card::Remodel, Card::Smithy, Card::Village, Card::Woodcutter, Card::Workshop]);
+        assert_eq!(g.board_state().affordable(0).collect::<Vec<Card>>(), vec![Card::Copper, Card::Curse]);
+    }
+    #[test]
+    fn concede_ends_game() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        assert_eq!(g.result(), None);
+        g.act(Action::Concede).unwrap();
+        assert_eq!(g.state(), State::GameOver);
+        assert_eq!(g.result(), Some(GameResult { winners: vec![Player::P1], losers: vec![Player::P0] }));
+        assert!(g.act(Action::EndAction).is_err());
+        assert!(g.act(Action::Concede).is_err());
+    }
+    #[test]
+    fn board_without_a_turn_not_started() {
+        let board = BoardState::builder().players(Players::Two).supply(Card::Copper, 10).build().unwrap();
+        let mut g = Game::from_state(board).unwrap();
+        assert_eq!(g.state(), State::NotStarted);
+        assert!(g.valid_actions().is_empty());
+        assert_eq!(g.act(Action::EndAction), Err(ActionError::WrongState(State::NotStarted)));
+        assert_eq!(g.act(Action::Concede), Err(ActionError::WrongState(State::NotStarted)));
+        let mut empty = Game::from_mutations(&Vec::new()).unwrap();
+        assert_eq!(empty.state(), State::NotStarted);
+        assert!(empty.valid_actions().is_empty());
+        assert!(!empty.can_play_any_action());
+        assert_eq!(empty.act(Action::Buy(Card::Copper)), Err(ActionError::WrongState(State::NotStarted)));
+    }
+    #[test]
+    fn builder_position_can_be_played() {
+        let board = BoardState::builder()
+            .players(Players::Two)
+            .supply(Card::Silver, 10)
+            .supply(Card::Smithy, 10)
+            .player_hand(Player::P0, Card::Smithy, 1)
+            .player_hand(Player::P0, Card::Copper, 3)
+            .player_deck(Player::P0, Card::Estate, 2)
+            .player_deck(Player::P0, Card::Silver, 1)
+            .active_player(Player::P0, PlayerPhase::Action)
+            .build()
+            .unwrap();
+        let mut g = Game::from_state(board).unwrap();
+        assert_eq!(g.state(), State::ActionPhase);
+        g.act(Action::PlayAction(Card::Smithy)).unwrap();
+        // The cards added last are drawn first
+        assert_eq!(g.active_player_state().known_hand().count(Card::Silver), 1);
+        assert_eq!(g.active_player_state().known_hand().count(Card::Estate), 2);
+        g.act(Action::EndAction).unwrap();
+        g.auto_play_treasures();
+        g.act(Action::Buy(Card::Smithy)).unwrap();
+        assert_eq!(g.active_player_state().get_buys(), 0);
+        assert_eq!(BoardState::builder().players(Players::Two).active_player(Player::P3, PlayerPhase::Buy).build(), None);
+    }
+    #[test]
+    fn game_ended_event_on_concede() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        let events = g.observe();
+        g.act(Action::EndAction).unwrap();
+        assert_eq!(events.try_iter().count(), 0);
+        g.act(Action::Concede).unwrap();
+        assert_eq!(events.try_iter().collect::<Vec<Event>>(), vec![Event::GameEnded(g.result().unwrap())]);
+        assert!(g.act(Action::EndBuy).is_err());
+        assert_eq!(events.try_iter().count(), 0);
+    }
+    #[test]
+    fn subscriber_receives_mutations() {
+        let mut g = Game::new_first_game(Players::Two).0;
+        let stream = g.subscribe();
+        let mutations = g.act(Action::EndAction).unwrap();
+        assert_eq!(stream.try_iter().collect::<Mutations>(), mutations);
+        let mutations = g.act(Action::EndBuy).unwrap();
+        assert_eq!(stream.try_iter().collect::<Mutations>(), mutations);
+        // Refused actions do not produce anything
+        assert!(g.act(Action::EndBuy).is_err());
+        assert_eq!(stream.try_iter().count(), 0);
+    }
+    #[test]
+    fn last_province_emits_pile_emptied() {
+        let mut g = Game::from_mutations(&vec![
+            Mutation::SetPlayers(Players::Two),
+            Mutation::AddStack(Card::Province, 1),
+        ]).unwrap();
+        let events = g.observe();
+        assert!(g.apply_mutations(&vec![Mutation::GainCard(Player::P0, Card::Province)]));
+        // Emptying the Province pile also ends the game
+        assert_eq!(events.try_iter().collect::<Vec<Event>>(), vec![Event::PileEmptied(Card::Province),
+            Event::GameEnded(GameResult { winners: vec![Player::P0], losers: vec![Player::P1] })]);
+        // The pile is already empty so nothing further can happen
+        assert!(!g.apply_mutations(&vec![Mutation::GainCard(Player::P1, Card::Province)]));
+        assert_eq!(events.try_iter().count(), 0);
+    }
 }
@@ -1,14 +1,86 @@
-use card::Card;
+use card::{self, Card, CardSet};
+use state::Player;
+use enum_map::EnumMap;
 
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Enum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Players {
     Two = 2,
     Three = 3,
     Four = 4,
+    Five = 5,
+    Six = 6,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Rules {
     pub players: Players,
     pub set: [Card; 10],
+    /// Override the size of the victory card piles left in the supply once starting decks are dealt
+    ///
+    /// When present the entries for victory cards replace `Card::starting_count`, with the Estates
+    /// dealt into starting decks added on top. Entries for any other card are ignored.
+    pub victory_pile_sizes: Option<EnumMap<Card, u32>>,
+    /// Player that takes the first turn
+    pub starting_player: Player,
+    /// Whether the supply has a Curse pile
+    ///
+    /// A common variant only includes Curses when the kingdom has an attack to give them out,
+    /// which is `set.iter().any(|c| c.is_attack())`.
+    pub curse_pile: bool,
+    /// Whether drawing from an empty deck shuffles the discard first
+    ///
+    /// This is the standard rule. Turning it off makes draws from an empty deck fail, which is
+    /// useful for testing effects that assume draws always succeed.
+    pub auto_shuffle: bool,
+}
+
+impl Rules {
+    /// Standard rules for the given players and kingdom set
+    pub fn new(players: Players, set: [Card; 10]) -> Rules {
+        Rules {
+            players,
+            set,
+            victory_pile_sizes: None,
+            starting_player: Player::P0,
+            curse_pile: true,
+            auto_shuffle: true,
+        }
+    }
+    /// Number of cards the supply pile of `card` starts with
+    pub fn starting_count(&self, card: Card) -> u32 {
+        match self.victory_pile_sizes {
+            Some(ref sizes) if card::lists::BASE_VICTORY.contains(&card) => sizes[card] + self.dealt_count(card),
+            _ => card.starting_count(self.players),
+        }
+    }
+    /// Number of `card` dealt from the supply into the starting decks
+    fn dealt_count(&self, card: Card) -> u32 {
+        card::lists::STARTING_DECK.iter()
+            .filter(|(c, _)| *c == card)
+            .map(|(_, count)| count * self.players as u32)
+            .sum()
+    }
+    /// Every supply pile the game starts with, along with its starting size
+    ///
+    /// Piles are listed in the order they are added to the board: base treasures, base victory
+    /// cards, the kingdom and then Curses.
+    pub fn supply_piles(&self) -> Vec<(Card, u32)> {
+        let curses = if self.curse_pile { &[Card::Curse][..] } else { &[] };
+        card::lists::BASE_TREASURE.iter()
+            .chain(card::lists::BASE_VICTORY.iter())
+            .chain(self.set.iter())
+            .chain(curses.iter())
+            .map(|card| (*card, self.starting_count(*card)))
+            .collect()
+    }
+    /// Every card that enters the game
+    ///
+    /// Starting decks are dealt from the supply, so this is just the supply piles. This is what
+    /// `BoardState::assert_conserved` expects for a game created with these rules.
+    pub fn card_pool(&self) -> CardSet {
+        self.supply_piles().into_iter().collect()
+    }
 }
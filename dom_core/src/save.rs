@@ -0,0 +1,161 @@
+use rules::Rules;
+use state::{ApplyError, Mutations, Player, RNGSeed};
+use {Decision, Game};
+
+use serde_json;
+use std::fmt;
+use std::iter;
+
+/// Version of the saved game format written by `save_game`
+pub const SAVE_VERSION: u32 = 1;
+
+/// Just enough of a saved game to check its version before reading the rest
+#[derive(Deserialize)]
+struct Envelope {
+    version: u32,
+}
+
+/// A game as it is saved
+///
+/// The game is recreated from its rules and seed, and then the history is replayed on top of it.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    version: u32,
+    rules: Rules,
+    seed: RNGSeed,
+    open: bool,
+    /// Batches of mutations applied after setting up the game, along with the decisions pending
+    /// before each
+    history: Vec<(Mutations, Vec<Decision>)>,
+    /// Decisions pending after the last batch
+    pending: Vec<Decision>,
+}
+
+/// Reasons a saved game could not be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// The save is not valid JSON or is missing parts of the game
+    Json(serde_json::Error),
+    /// The save is from a version of the format this does not know how to read
+    UnknownVersion(u32),
+    /// The history of the save does not replay onto the game its rules and seed create
+    Replay(ApplyError),
+    /// The rules or a decision name a player that is not in the game
+    NotInGame(Player),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Json(ref err) => write!(f, "Saved game is malformed: {}", err),
+            LoadError::UnknownVersion(version) =>
+                write!(f, "Saved game has version {} but only version {} is supported", version, SAVE_VERSION),
+            LoadError::Replay(ref err) => write!(f, "Saved game does not replay: {}", err),
+            LoadError::NotInGame(player) => write!(f, "Saved game refers to {:?}, who is not in the game", player),
+        }
+    }
+}
+
+/// Save `game`, which was created from `rules`, as JSON
+///
+/// Everything that can be undone is saved, so the loaded game can be undone as far as the
+/// original. Returns `None` for games that cannot be recreated from `rules`, their seed and
+/// history, such as those built from mutations or whose board was edited directly.
+pub fn save_game(rules: &Rules, game: &Game) -> Option<String> {
+    let saved = SavedGame {
+        version: SAVE_VERSION,
+        rules: rules.clone(),
+        seed: game.board_state().seed()?,
+        open: game.open,
+        history: game.history.iter()
+            .flat_map(|history| history.batches())
+            .map(|batch| (batch.mutations.clone(), batch.pending.clone()))
+            .collect(),
+        pending: game.pending.clone(),
+    };
+    let json = serde_json::to_string(&saved).expect("Saved games always serialize");
+    // Check the save recreates the game, rather than finding out when loading it
+    match load_game(&json) {
+        Ok((_, loaded)) if loaded.board_state() == game.board_state() => Some(json),
+        _ => None,
+    }
+}
+
+/// Load a game saved by `save_game`, along with the rules it was created from
+///
+/// Saves from any other version of the format are rejected rather than risk misreading them.
+pub fn load_game(json: &str) -> Result<(Rules, Game), LoadError> {
+    let envelope: Envelope = serde_json::from_str(json).map_err(LoadError::Json)?;
+    if envelope.version != SAVE_VERSION {
+        return Err(LoadError::UnknownVersion(envelope.version));
+    }
+    let saved: SavedGame = serde_json::from_str(json).map_err(LoadError::Json)?;
+    // Creating and replaying the game assume every player it names is in the game
+    let decisions = saved.history.iter().flat_map(|(_, pending)| pending).chain(&saved.pending);
+    let mut players = iter::once(saved.rules.starting_player).chain(decisions.map(Decision::player));
+    if let Some(player) = players.find(|p| !Player::iter_players(saved.rules.players).any(|q| q == p)) {
+        return Err(LoadError::NotInGame(player));
+    }
+    let (mut game, _) = Game::new_from_seed_with(saved.rules.clone(), saved.seed, saved.open);
+    for (mutations, pending) in saved.history {
+        game.pending = pending;
+        game.try_apply_mutations(&mutations).map_err(LoadError::Replay)?;
+    }
+    game.pending = saved.pending;
+    Ok((saved.rules, game))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::{self, Card};
+    use rules::Players;
+    use state::Player;
+    use {Action, State};
+    #[test]
+    fn save_and_load() {
+        let rules = Rules::new(Players::Two, card::lists::FIRST_SET);
+        let (mut g, _) = Game::new_from_seed(rules.clone(), ::tests::DUMMY_SEED);
+        g.act(Action::EndAction).unwrap();
+        g.auto_play_treasures();
+        g.act(Action::EndBuy).unwrap();
+        g.act(Action::EndAction).unwrap();
+        let json = save_game(&rules, &g).unwrap();
+        let (loaded_rules, mut loaded) = load_game(&json).unwrap();
+        assert_eq!(loaded_rules.set, rules.set);
+        assert_eq!(loaded.board_state(), g.board_state());
+        assert_eq!(loaded.state(), State::BuyPhase);
+        assert_eq!(loaded.board_state().active_player(), Player::P1);
+        // The loaded game carries on and undoes exactly like the original
+        assert_eq!(loaded.undo_last(), g.undo_last());
+        assert_eq!(loaded.board_state(), g.board_state());
+        assert_eq!(loaded.act(Action::PlayTreasure(Card::Copper)), g.act(Action::PlayTreasure(Card::Copper)));
+        // Games that cannot be recreated are not saved
+        let copy = Game::from_state(g.board_state().clone()).unwrap();
+        assert!(save_game(&rules, &copy).is_none());
+        assert!(save_game(&Rules::new(Players::Three, card::lists::FIRST_SET), &g).is_none());
+        let unseeded = Game::from_mutations(&Vec::new()).unwrap();
+        assert!(save_game(&rules, &unseeded).is_none());
+    }
+    #[test]
+    fn unknown_version_rejected() {
+        let rules = Rules::new(Players::Two, card::lists::FIRST_SET);
+        let (g, _) = Game::new_from_seed(rules.clone(), ::tests::DUMMY_SEED);
+        let json = save_game(&rules, &g).unwrap().replace("\"version\":1", "\"version\":99");
+        match load_game(&json) {
+            Err(LoadError::UnknownVersion(99)) => {},
+            other => panic!("Expected an unknown version, got {:?}", other.map(|_| ())),
+        }
+        assert!(matches!(load_game("{}"), Err(LoadError::Json(_))));
+    }
+    #[test]
+    fn players_outside_the_game_rejected() {
+        let rules = Rules::new(Players::Two, card::lists::FIRST_SET);
+        let (g, _) = Game::new_from_seed(rules.clone(), ::tests::DUMMY_SEED);
+        let json = save_game(&rules, &g).unwrap();
+        let starting = json.replace("\"starting_player\":\"P0\"", "\"starting_player\":\"P5\"");
+        assert!(matches!(load_game(&starting), Err(LoadError::NotInGame(Player::P5))));
+        let pending = json.replace("\"pending\":[]", "\"pending\":[{\"CellarDiscard\":{\"player\":\"P3\",\"max\":1}}]");
+        assert!(matches!(load_game(&pending), Err(LoadError::NotInGame(Player::P3))));
+    }
+}
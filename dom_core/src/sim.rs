@@ -0,0 +1,139 @@
+use agent::Agent;
+use rules::Rules;
+use state::{BoardState, Player, RNGSeed};
+use {Game, SimulateError};
+
+use std::ops::Range;
+use std::thread;
+
+/// Totals over many simulated games, see `simulate_many`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Stats {
+    /// Games simulated
+    pub games: u32,
+    /// Games won by each player, indexed by player. Ties count as a win for every winner
+    pub wins: Vec<u32>,
+    /// Games that did not finish, as they hit the turn limit or an agent was refused
+    pub unfinished: u32,
+    /// Turns started across every finished game
+    pub turns: u64,
+}
+
+impl Stats {
+    /// Add the final board of a game, or the reason it stopped, to the totals
+    fn record(&mut self, game: &Game, outcome: Result<BoardState, SimulateError>) {
+        self.games += 1;
+        let result = match (outcome, game.result()) {
+            (Ok(board), Some(result)) => {
+                self.turns += board.turn_number() as u64;
+                result
+            },
+            _ => {
+                self.unfinished += 1;
+                return;
+            },
+        };
+        for winner in result.winners {
+            if self.wins.len() <= winner as usize {
+                self.wins.resize(winner as usize + 1, 0);
+            }
+            self.wins[winner as usize] += 1;
+        }
+    }
+    /// Add the totals of `other` to these
+    pub fn merge(&mut self, other: &Stats) {
+        self.games += other.games;
+        self.unfinished += other.unfinished;
+        self.turns += other.turns;
+        if self.wins.len() < other.wins.len() {
+            self.wins.resize(other.wins.len(), 0);
+        }
+        for (wins, other) in self.wins.iter_mut().zip(&other.wins) {
+            *wins += *other;
+        }
+    }
+    /// Games won by `player`
+    pub fn wins(&self, player: Player) -> u32 {
+        self.wins.get(player as usize).cloned().unwrap_or(0)
+    }
+}
+
+/// Seed of the game numbered `game` in a run from the `base` seed
+///
+/// The game number is mixed into the first bytes of the base seed, so each game of a run gets
+/// its own seed whichever thread plays it.
+pub fn game_seed(base: RNGSeed, game: u32) -> RNGSeed {
+    let mut seed = base;
+    for (byte, number) in seed.iter_mut().zip(game.to_le_bytes().iter()) {
+        *byte ^= number;
+    }
+    seed
+}
+
+/// Play the numbered `games` from the `base` seed one after the other
+///
+/// Each game is created from `rules` and the seed given by `game_seed`, with `agents` making
+/// the agents for each seat from that same seed. Games still going after `max_turns` turns are
+/// counted as unfinished.
+pub fn simulate_many<F>(rules: &Rules, agents: F, base: RNGSeed, games: Range<u32>, max_turns: u32) -> Stats
+    where F: Fn(RNGSeed) -> Vec<Box<dyn Agent>> {
+    let mut stats = Stats::default();
+    for number in games {
+        let seed = game_seed(base, number);
+        let (mut game, _) = Game::new_from_seed(rules.clone(), seed);
+        let outcome = game.simulate(&mut agents(seed), max_turns);
+        stats.record(&game, outcome);
+    }
+    stats
+}
+
+/// Like `simulate_many`, but splits the games between `threads` worker threads
+///
+/// Every game only depends on its seed, so the stats are identical to `simulate_many` over the
+/// same games however they are split.
+pub fn simulate_many_parallel<F>(rules: &Rules, agents: F, base: RNGSeed, games: Range<u32>, max_turns: u32, threads: u32) -> Stats
+    where F: Fn(RNGSeed) -> Vec<Box<dyn Agent>> + Sync {
+    let count = games.end.saturating_sub(games.start);
+    let chunk = count.div_ceil(threads.max(1)).max(1);
+    let agents = &agents;
+    thread::scope(|scope| {
+        let workers: Vec<_> = games.clone()
+            .step_by(chunk as usize)
+            .map(|start| {
+                let end = start.saturating_add(chunk).min(games.end);
+                scope.spawn(move || simulate_many(rules, agents, base, start..end, max_turns))
+            })
+            .collect();
+        workers.into_iter().fold(Stats::default(), |mut stats, worker| {
+            stats.merge(&worker.join().expect("Simulation thread panicked"));
+            stats
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent::{BigMoney, RandomAgent};
+    use card;
+    use rules::Players;
+    fn agents(seed: RNGSeed) -> Vec<Box<dyn Agent>> {
+        vec![Box::new(BigMoney), Box::new(RandomAgent::new(seed))]
+    }
+    #[test]
+    fn parallel_matches_sequential() {
+        let rules = Rules::new(Players::Two, card::lists::FIRST_SET);
+        let sequential = simulate_many(&rules, agents, ::tests::DUMMY_SEED, 0..10, 100);
+        assert_eq!(sequential.games, 10);
+        // Big Money always beats buying at random
+        assert_eq!(sequential.unfinished, 0);
+        assert_eq!(sequential.wins(Player::P0), 10);
+        assert_eq!(sequential.wins(Player::P1), 0);
+        for threads in 1..5 {
+            assert_eq!(simulate_many_parallel(&rules, agents, ::tests::DUMMY_SEED, 0..10, 100, threads), sequential);
+        }
+        // Each game has its own seed
+        assert_ne!(game_seed(::tests::DUMMY_SEED, 0), game_seed(::tests::DUMMY_SEED, 1));
+        assert_eq!(simulate_many_parallel(&rules, agents, ::tests::DUMMY_SEED, 3..3, 100, 4), Stats::default());
+    }
+}
@@ -1,23 +1,31 @@
 use rand::SeedableRng;
 use rand::Rng;
 
-use card::{Card, CardSet};
+use card::{self, Card, CardSet};
 use rules::Players;
 
 use std::slice;
+use std::iter;
+use std::fmt::{self, Write};
+use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u32)]
 pub enum Player {
     P0 = 0,
     P1 = 1,
     P2 = 2,
     P3 = 3,
+    P4 = 4,
+    P5 = 5,
 }
 
 const PLAYER_LIST_TWO: [Player; 2] = [Player::P0, Player::P1];
 const PLAYER_LIST_THREE: [Player; 3] = [Player::P0, Player::P1, Player::P2];
 const PLAYER_LIST_FOUR: [Player; 4] = [Player::P0, Player::P1, Player::P2, Player::P3];
+const PLAYER_LIST_FIVE: [Player; 5] = [Player::P0, Player::P1, Player::P2, Player::P3, Player::P4];
+const PLAYER_LIST_SIX: [Player; 6] = [Player::P0, Player::P1, Player::P2, Player::P3, Player::P4, Player::P5];
 
 impl Player {
     pub fn next(&self, players: Players) -> Player {
@@ -30,30 +38,72 @@ impl Player {
             Players::Two => PLAYER_LIST_TWO.iter(),
             Players::Three => PLAYER_LIST_THREE.iter(),
             Players::Four => PLAYER_LIST_FOUR.iter(),
+            Players::Five => PLAYER_LIST_FIVE.iter(),
+            Players::Six => PLAYER_LIST_SIX.iter(),
         }.into_iter()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlayerSet {
     bitset: u32,
 }
 
 impl PlayerSet {
+    pub fn empty() -> PlayerSet {
+        PlayerSet {
+            bitset: 0,
+        }
+    }
     pub fn just(p: Player) -> PlayerSet{
         PlayerSet {
             bitset: 1 << (p as u32),
         }
     }
+    /// Every player in a game of `players`
+    pub fn all(players: Players) -> PlayerSet {
+        let mut set = PlayerSet::empty();
+        for p in Player::iter_players(players) {
+            set.insert(*p);
+        }
+        set
+    }
+    /// Every player in a game of `players` except `active`, such as the targets of an attack
+    pub fn others(active: Player, players: Players) -> PlayerSet {
+        let mut set = PlayerSet::all(players);
+        set.bitset &= !PlayerSet::just(active).bitset;
+        set
+    }
     pub fn contains(&self, p: Player) -> bool {
         ((self.bitset >> (p as u32)) & 1) == 1
     }
+    pub fn insert(&mut self, p: Player) {
+        self.bitset |= 1 << (p as u32);
+    }
+    /// Iterate the members of the set in seat order, from `Player::P0`
+    pub fn iter(&self, players: Players) -> impl Iterator<Item = Player> {
+        self.iter_from(Player::P0, players)
+    }
+    /// Iterate the members of the set in turn order, starting from `first`
+    ///
+    /// Effects on the opponents of the active player start from the player that follows them,
+    /// which is `active.next(players)`.
+    pub fn iter_from(&self, first: Player, players: Players) -> impl Iterator<Item = Player> {
+        let set = *self;
+        let seats = Player::iter_players(players);
+        let start = seats.clone().position(|p| *p == first).unwrap_or(0);
+        seats.clone().skip(start).chain(seats.take(start))
+            .cloned()
+            .filter(move |p| set.contains(*p))
+    }
 }
 
 /// Cards are revealed from the hand of a player and are shown to a single player
 /// or all players. Having an 'all' option instead of requiring multiple reveals
 /// provides an indication of whether a reveal was public or directed
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Reveal {
     All,
     Just(PlayerSet),
@@ -72,7 +122,8 @@ pub enum Reveal {
 /// get the hidden information. Replaying up until the current state does not need the seed
 /// as state that may be hidden has an explicit reveal `Mutation` before being used.
 /// Reveals can be directed to a subset of players to describe partial information.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Mutation {
     /// Add players to the game
     ///
@@ -96,6 +147,10 @@ pub enum Mutation {
     SetBuys(Player, u32),
     SetActions(Player, u32),
     SetGold(Player, u32),
+    /// Set how many cards a player draws for their next hand during cleanup
+    ///
+    /// The value persists, so effects that only change a single hand must set it back.
+    SetNextHandSize(Player, u32),
     /// Discard a card from hand to discard pile
     ///
     /// Cards are always publicly revealed at the point they are discarded
@@ -120,18 +175,77 @@ pub enum Mutation {
     DrawCard(Player, Option<Card>),
     /// Move a card from hand to play area
     PlayCard(Player, Card),
+    /// Move a card from hand to the trash
+    ///
+    /// Like discarding, the card is publicly revealed as it is trashed.
+    TrashHand(Player, Card),
     /// Gain a card from supply to discard
     GainCard(Player, Card),
+    /// Gain a card from supply directly into hand
+    GainToHand(Player, Card),
+    /// Gain a card from supply onto the top of the deck
+    ///
+    /// The card is publicly known, so the top of the deck is known until it is drawn.
+    GainToDeck(Player, Card),
+    /// Forget the cards a player has gained this turn
+    ClearGained(Player),
+    /// Set whether a player is protected from attacks this turn
+    ///
+    /// A player becomes protected by revealing a Moat in reaction to an attack.
+    SetProtected(Player, bool),
     /// Shuffle discard and make it the deck
     ///
     /// This implies that there is no current deck
     ShuffleDiscard(Player),
+    /// Shuffle discard and put it under the current deck
+    ///
+    /// Unlike `ShuffleDiscard` the deck does not need to be empty, which is needed by effects that
+    /// force a shuffle. The cards already in the deck stay on top in the same order.
+    ShuffleInDiscard(Player),
+    /// Player resigns from the game
+    ///
+    /// This immediately ends the game. Only a single player can concede.
+    Concede(Player),
+    /// Marks the end of game setup
+    ///
+    /// Everything prior to this is the initial layout of the supply and the dealing of starting
+    /// decks and hands. It has no effect on the board and exists so that logs can separate setup
+    /// from play.
+    BeginGame,
+    /// Group of mutations that form a single logical step
+    ///
+    /// The batch is applied atomically, either every mutation in it succeeds or the batch as a
+    /// whole fails.
+    Batch(Mutations),
+}
+
+impl Mutation {
+    /// The mutation as seen by `viewer`
+    ///
+    /// Cards drawn by other players and hand reveals that are not shown to `viewer` have their
+    /// cards hidden. Everything else, including shuffles, is public and unchanged.
+    pub fn obscure_for(&self, viewer: Player) -> Mutation {
+        match *self {
+            Mutation::DrawCard(p, _) if p != viewer => Mutation::DrawCard(p, None),
+            Mutation::RevealHandCards(p, _, Reveal::Just(to)) if p != viewer && !to.contains(viewer) =>
+                Mutation::RevealHandCards(p, None, Reveal::Just(to)),
+            Mutation::Batch(ref batch) =>
+                Mutation::Batch(batch.iter().map(|m| m.obscure_for(viewer)).collect()),
+            ref other => other.clone(),
+        }
+    }
 }
 
 /// Convenience alias for grouping ordered mutations
 pub type Mutations = Vec<Mutation>;
 
+/// The mutations as seen by `viewer`, see `Mutation::obscure_for`
+pub fn obscure_mutations(mutations: Mutations, viewer: Player) -> Mutations {
+    mutations.iter().map(|m| m.obscure_for(viewer)).collect()
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PlayerPhase {
     Action,
     Buy,
@@ -139,8 +253,14 @@ pub enum PlayerPhase {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlayerState {
-    hand: Vec<Option<Card>>,
+    /// Cards in hand that are known
+    ///
+    /// A hand has no order, so it is kept as a set of known cards and a count of unknown cards.
+    hand: CardSet,
+    /// Number of cards in hand that are not known
+    hand_unknown: u32,
     played: CardSet,
     discard: CardSet,
     draw: Vec<Option<Card>>,
@@ -148,11 +268,29 @@ pub struct PlayerState {
     buys: u32,
     phase: PlayerPhase,
     gold: u32,
+    gained: Vec<Card>,
+    shuffles: u32,
+    next_hand: u32,
+    /// Whether a Moat has been revealed this turn to block attacks
+    protected: bool,
+    /// Number of turns the player has started
+    turns: u32,
 }
 
 impl PlayerState {
-    pub fn hand_iter(&self) -> impl Iterator<Item = Option<Card>> {
-        self.hand.clone().into_iter()
+    /// Cards in hand, known cards first followed by any unknown cards
+    pub fn hand_iter(&self) -> impl Iterator<Item = Option<Card>> + '_ {
+        self.hand.count_iter()
+            .flat_map(|(card, count)| iter::repeat_n(Some(card), *count as usize))
+            .chain(iter::repeat_n(None, self.hand_unknown as usize))
+    }
+    /// Number of cards in hand, including any unknown cards
+    pub fn hand_size(&self) -> usize {
+        (self.hand.total_count() + self.hand_unknown) as usize
+    }
+    /// The cards in hand that are known
+    pub fn known_hand(&self) -> CardSet {
+        self.hand
     }
     pub fn played_iter(&self) -> impl Iterator<Item = Card> {
         self.played.into_iter()
@@ -160,8 +298,12 @@ impl PlayerState {
     pub fn discard_iter(&self) -> impl Iterator<Item = Card> {
         self.discard.into_iter()
     }
-    pub fn draw_iter(&self) -> impl Iterator<Item = Option<Card>> {
-        self.draw.clone().into_iter().rev()
+    pub fn draw_iter(&self) -> impl Iterator<Item = Option<Card>> + '_ {
+        self.draw.iter().rev().cloned()
+    }
+    /// Cards gained this turn in the order they were gained
+    pub fn gained_iter(&self) -> impl Iterator<Item = Card> + '_ {
+        self.gained.iter().cloned()
     }
     pub fn get_phase(&self) -> PlayerPhase {
         self.phase
@@ -175,17 +317,151 @@ impl PlayerState {
     pub fn get_gold(&self) -> u32 {
         self.gold
     }
+    /// Number of cards drawn for the next hand at cleanup
+    pub fn get_next_hand_size(&self) -> u32 {
+        self.next_hand
+    }
+    /// Number of turns the player has taken, including the current one
+    pub fn get_turns_taken(&self) -> u32 {
+        self.turns
+    }
+    /// Whether the player has revealed a Moat this turn and so is unaffected by attacks
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+    /// Number of times the discard pile has been shuffled into a new deck
+    pub fn get_shuffle_count(&self) -> u32 {
+        self.shuffles
+    }
+    /// Remove a card from the hand
+    ///
+    /// If the specific card is not known to be in the hand an unknown card is removed instead.
+    fn take_from_hand(&mut self, card: Card) -> Option<()> {
+        if !self.hand.take(card, 1) {
+            self.hand_unknown = self.hand_unknown.checked_sub(1)?;
+        }
+        Some(())
+    }
+    /// Put a card into the hand
+    fn put_in_hand(&mut self, card: Option<Card>) {
+        match card {
+            Some(card) => self.hand.insert(card, 1),
+            None => self.hand_unknown += 1,
+        }
+    }
+}
+
+/// Comma separated list of cards with each run of unknown cards summarised as 'N unknown'
+fn summary_list<I: Iterator<Item = Option<Card>>>(cards: I) -> String {
+    let mut parts = Vec::new();
+    let mut unknown = 0;
+    for card in cards {
+        match card {
+            Some(card) => {
+                if unknown > 0 {
+                    parts.push(format!("{} unknown", unknown));
+                    unknown = 0;
+                }
+                parts.push(card.to_string());
+            },
+            None => unknown += 1,
+        }
+    }
+    if unknown > 0 {
+        parts.push(format!("{} unknown", unknown));
+    }
+    parts.join(", ")
+}
+
+/// Space separated list of cards with unknown cards shown as '?'
+fn card_list<I: Iterator<Item = Option<Card>>>(cards: I) -> String {
+    cards.map(|card| card.map_or("?".to_string(), |c| format!("{:?}", c)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Like `card_list` but in a fixed order, for zones whose order has no meaning
+fn sorted_card_list<I: Iterator<Item = Option<Card>>>(cards: I) -> String {
+    let mut cards: Vec<Option<Card>> = cards.collect();
+    cards.sort_by_key(|card| card.map_or(u32::MAX, |c| c as u32));
+    card_list(cards.into_iter())
 }
 
 impl PartialEq for PlayerState {
     fn eq(&self, other: &PlayerState) -> bool {
         self.hand == other.hand
+            && self.hand_unknown == other.hand_unknown
             && self.played == other.played
             && self.discard == other.discard
             && self.draw.iter().eq(other.draw.iter())
     }
 }
 
+impl Eq for PlayerState {}
+
+/// Summary of the player for display to a person
+///
+/// Only the size of the discard is shown, as it is not looked through during play.
+impl fmt::Display for PlayerState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Hand: [{}]", summary_list(self.hand_iter()))?;
+        writeln!(f, "Played: [{}]", summary_list(self.played_iter().map(Some)))?;
+        writeln!(f, "Deck: [{}]", summary_list(self.draw_iter()))?;
+        writeln!(f, "Discard: {} cards", self.discard.total_count())?;
+        writeln!(f, "Actions: {} Buys: {} Gold: {}", self.actions, self.buys, self.gold)
+    }
+}
+
+/// Hashes the same zones that `PartialEq` compares
+impl Hash for PlayerState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hand.hash(state);
+        self.hand_unknown.hash(state);
+        self.played.hash(state);
+        self.discard.hash(state);
+        self.draw.hash(state);
+    }
+}
+
+/// Reasons a board state can fail validation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidationError {
+    /// More copies of a card are known to be on the board than ever entered the game
+    ExcessCards(Card, u32),
+    /// The number of cards on the board differs from the number that entered the game
+    CardCount { found: u32, expected: u32 },
+    /// A player other than the active player is in the middle of a turn
+    PhaseOutOfTurn(Player, PlayerPhase),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::ExcessCards(card, count) =>
+                write!(f, "{} copies of {:?} is more than entered the game", count, card),
+            ValidationError::CardCount { found, expected } =>
+                write!(f, "{} cards on the board but {} entered the game", found, expected),
+            ValidationError::PhaseOutOfTurn(player, phase) =>
+                write!(f, "Inactive player {:?} is in phase {:?}", player, phase),
+        }
+    }
+}
+
+/// Failure to apply mutations to a board
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyError {
+    /// Position of the first mutation that could not be applied
+    pub index: usize,
+    /// The mutation that could not be applied
+    pub mutation: Mutation,
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mutation {} ({:?}) is not valid for the board", self.index, self.mutation)
+    }
+}
+
 pub type RNGSource = ::rand::prng::chacha::ChaChaRng;
 pub type RNGSeed = [u8; 32];
 
@@ -193,13 +469,26 @@ pub type RNGSeed = [u8; 32];
 ///
 /// This structure is immutable and any mutations must be done through an explicit `Mutation`.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BoardState {
     supply: CardSet,
     trash: Vec<Card>,
     stacks: CardSet,
+    /// Every card that has entered the game
+    pool: CardSet,
     players: Vec<PlayerState>,
+    /// Not serialized, a deserialized board has no randomness source and cannot shuffle
+    #[cfg_attr(feature = "serde", serde(skip))]
     rand: Option<RNGSource>,
+    /// Seed `rand` was created from
+    seed: Option<RNGSeed>,
     turn: Player,
+    conceded: Option<Player>,
+    /// Number of turns started in the game
+    ///
+    /// Like `turn` this is not compared by `PartialEq`, so boards reached on different turns
+    /// compare equal.
+    turn_number: u32,
 }
 
 impl PartialEq for BoardState {
@@ -208,6 +497,42 @@ impl PartialEq for BoardState {
             && self.stacks == other.stacks
             && self.trash.iter().eq(other.trash.iter())
             && self.players.iter().eq(other.players.iter())
+            && self.conceded == other.conceded
+    }
+}
+
+impl Eq for BoardState {}
+
+/// Summary of the shared parts of the board for display to a person
+///
+/// Players are not included, display them with `PlayerState`'s `Display` from the perspective
+/// being shown.
+impl fmt::Display for BoardState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Supply:")?;
+        for card in self.supply_cards() {
+            match self.supply.count(card) {
+                0 => writeln!(f, "\t{}: DEPLETED", card)?,
+                count => writeln!(f, "\t{}: {}", card, count)?,
+            }
+        }
+        writeln!(f, "Trash:")?;
+        for card in &self.trash {
+            writeln!(f, "\t{}", card)?;
+        }
+        writeln!(f, "It is player {}'s turn", self.turn as u32 + 1)
+    }
+}
+
+/// Like `PartialEq` the RNG, whose turn it is and the turn number are not hashed, so boards that
+/// compare equal hash the same. `Game`'s hash also covers the turn.
+impl Hash for BoardState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.supply.hash(state);
+        self.stacks.hash(state);
+        self.trash.hash(state);
+        self.players.hash(state);
+        self.conceded.hash(state);
     }
 }
 
@@ -217,151 +542,277 @@ impl BoardState {
             supply: CardSet::empty(),
             trash: Vec::new(),
             stacks: CardSet::empty(),
+            pool: CardSet::empty(),
             players: Vec::new(),
             rand: seed.map(RNGSource::from_seed),
+            seed,
             turn: Player::P0,
+            conceded: None,
+            turn_number: 0,
         }
     }
+    /// Start building a board for a test or scenario, see `BoardStateBuilder`
+    pub fn builder() -> BoardStateBuilder {
+        BoardStateBuilder::default()
+    }
+    /// Seed the board's randomness source was created from
+    ///
+    /// A board created with the same seed and mutations makes the same shuffles, which allows
+    /// hidden information to be recreated. Boards without randomness, such as those built from
+    /// a log, have no seed.
+    pub fn seed(&self) -> Option<RNGSeed> {
+        self.seed
+    }
     pub fn supply_stacks(&self) -> impl Iterator<Item = (Card, &u32)> {
         self.supply.count_iter().filter(move |(key, _)| self.stacks.contains(*key))
     }
+    /// Cards in the trash in the order they were trashed
+    pub fn trash_iter(&self) -> impl Iterator<Item = Card> + '_ {
+        self.trash.iter().cloned()
+    }
+    /// Number of copies of `card` in the trash
+    pub fn count_trash(&self, card: Card) -> u32 {
+        self.trash.iter().filter(|c| **c == card).count() as u32
+    }
+    /// Number of the current turn, counting turns of all players from 1
+    ///
+    /// This is 0 until the first turn starts.
+    pub fn turn_number(&self) -> u32 {
+        self.turn_number
+    }
     pub fn active_player(&self) -> Player {
         self.turn
     }
+    /// The player that conceded the game, if any
+    pub fn conceded(&self) -> Option<Player> {
+        self.conceded
+    }
     pub fn get_player(&self, p: Player) -> Option<&PlayerState> {
         self.players.get(p as u32 as usize)
     }
+    pub fn active_player_state(&self) -> Option<&PlayerState> {
+        self.get_player(self.active_player())
+    }
     pub fn num_players(&self) -> Option<Players> {
         match self.players.len() {
             2 => Some(Players::Two),
             3 => Some(Players::Three),
             4 => Some(Players::Four),
+            5 => Some(Players::Five),
+            6 => Some(Players::Six),
             _ => None
         }
     }
-    fn set_players(self, p: Players) -> Option<BoardState> {
-        Some(self)
-            .filter(|x| x.players.len() == 0)
-            .map(|mut x| {
-                x.players.append(&mut [PlayerState {
-                    hand: Vec::new(),
-                    played: CardSet::empty(),
-                    discard: CardSet::empty(),
-                    draw: Vec::new(),
-                    actions: 0,
-                    buys: 0,
-                    phase: PlayerPhase::NotTurn,
-                    gold: 0,
-                }].iter().cycle().take(p as usize).cloned().collect());
-                x
-            })
+    fn set_players(&mut self, p: Players) -> Option<()> {
+        if !self.players.is_empty() {
+            return None;
+        }
+        self.players.append(&mut [PlayerState {
+            hand: CardSet::empty(),
+            hand_unknown: 0,
+            played: CardSet::empty(),
+            discard: CardSet::empty(),
+            draw: Vec::new(),
+            actions: 0,
+            buys: 0,
+            phase: PlayerPhase::NotTurn,
+            gold: 0,
+            gained: Vec::new(),
+            shuffles: 0,
+            next_hand: 5,
+            protected: false,
+            turns: 0,
+        }].iter().cycle().take(p as usize).cloned().collect());
+        Some(())
     }
-    fn add_stack(self, card: Card, count: u32) -> Option<BoardState> {
-        Some(self)
-            .filter(|x| !x.stacks.contains(card))
-            .map(|mut x| {
-                x.stacks.insert(card, 1);
-                x.supply.insert(card, count);
-                x}
-            )
-    }
-    fn gain_card(self, player: Player, card: Card) -> Option<BoardState> {
-        Some(self)
-            .and_then(|mut state| if state.supply.take(card, 1) { Some(state) } else { None })
-            .and_then(|mut state|
-                state.players.get_mut(player as usize)
-                    .map(|p| p.discard.insert(card, 1))
-                    .map(|_| state)
-            )
-    }
-    fn shuffle(self, player: Player) -> Option<BoardState> {
-        let mut b = self;
-        // Temporary scope so we can move `b` later on
-        {
-            let p = b.players.get_mut(player as usize)
-                .filter(|p| p.draw.len() == 0)?;
-            let discard = p.discard.drain();
-            // check if we have rng powers to shuffle
-            if let Some(mut rng) = b.rand.as_mut() {
-                p.draw = discard.map(|x| Some(x)).collect();
-                rng.shuffle(p.draw.as_mut_slice());
-            } else {
-                p.draw = discard.map(|_| None).collect();
-            }
+    fn add_stack(&mut self, card: Card, count: u32) -> Option<()> {
+        if self.stacks.contains(card) {
+            return None;
         }
-        Some(b)
+        self.stacks.insert(card, 1);
+        self.supply.insert(card, count);
+        self.pool.insert(card, count);
+        Some(())
     }
-    fn try_modify_player<F: Fn(&mut PlayerState) -> Option<()>>(self, player: Player, f: F) -> Option<BoardState> {
-        Some(self)
-            .and_then(|mut state| state.players.get_mut(player as usize)
-                .and_then(|player| f(player))
-                .map(|_| state)
-            )
+    fn gain_card(&mut self, player: Player, card: Card) -> Option<()> {
+        if !self.supply.contains(card) {
+            return None;
+        }
+        self.modify_player(player, |p| {p.discard.insert(card, 1); p.gained.push(card)})?;
+        self.supply.take(card, 1);
+        Some(())
+    }
+    fn gain_to_hand(&mut self, player: Player, card: Card) -> Option<()> {
+        if !self.supply.contains(card) {
+            return None;
+        }
+        self.modify_player(player, |p| {p.hand.insert(card, 1); p.gained.push(card)})?;
+        self.supply.take(card, 1);
+        Some(())
+    }
+    fn gain_to_deck(&mut self, player: Player, card: Card) -> Option<()> {
+        if !self.supply.contains(card) {
+            return None;
+        }
+        // The end of `draw` is the top of the deck
+        self.modify_player(player, |p| {p.draw.push(Some(card)); p.gained.push(card)})?;
+        self.supply.take(card, 1);
+        Some(())
+    }
+    fn shuffle(&mut self, player: Player) -> Option<()> {
+        if !self.get_player(player)?.draw.is_empty() {
+            return None;
+        }
+        self.shuffle_in(player)
+    }
+    fn shuffle_in(&mut self, player: Player) -> Option<()> {
+        let p = self.players.get_mut(player as usize)?;
+        // Shuffling an empty discard does not cycle the deck
+        if !p.discard.is_empty() {
+            p.shuffles += 1;
+        }
+        let discard = p.discard.drain();
+        // check if we have rng powers to shuffle
+        let shuffled: Vec<Option<Card>> = if let Some(rng) = self.rand.as_mut() {
+            let mut cards: Vec<Option<Card>> = discard.map(Some).collect();
+            rng.shuffle(cards.as_mut_slice());
+            cards
+        } else {
+            discard.map(|_| None).collect()
+        };
+        // The end of `draw` is the top of the deck
+        p.draw.splice(0..0, shuffled);
+        Some(())
+    }
+    /// Change a player with `f`, which must leave the player unchanged if it fails
+    fn try_modify_player<F: FnOnce(&mut PlayerState) -> Option<()>>(&mut self, player: Player, f: F) -> Option<()> {
+        self.players.get_mut(player as usize).and_then(f)
     }
-    fn modify_player<F: Fn(&mut PlayerState)>(self, player: Player, f: F) -> Option<BoardState> {
-        self.try_modify_player(player, |player| {f(player);Some(())})
+    fn modify_player<F: FnOnce(&mut PlayerState)>(&mut self, player: Player, f: F) -> Option<()> {
+        self.players.get_mut(player as usize).map(f)
     }
-    fn draw_card(self, player: Player, card: Option<Card>) -> Option<BoardState> {
+    fn draw_card(&mut self, player: Player, card: Option<Card>) -> Option<()> {
         self.try_modify_player(player,
             |player| {
-                let draw_card = player.draw.pop()
-                    .filter(|c| c.is_none() || card.is_none() || *c == card)?;
+                let draw_card = *player.draw.last()
+                    .filter(|c| c.is_none() || card.is_none() || **c == card)?;
+                player.draw.pop();
                 // Use the drawn card or provided card, whichever has the most information
-                player.hand.push(draw_card.or(card));
+                player.put_in_hand(draw_card.or(card));
                 Some(())
             }
         )
     }
-    fn change_turn(self, player: Player) -> Option<BoardState> {
-        Some(self)
-            .filter(|state| state.players.get(player as usize) != None)
-            .map(|mut state| {state.turn = player; state})
+    fn change_turn(&mut self, player: Player) -> Option<()> {
+        self.modify_player(player, |p| p.turns += 1)?;
+        self.turn = player;
+        self.turn_number += 1;
+        Some(())
     }
-    fn set_phase(self, player: Player, phase: PlayerPhase) -> Option<BoardState> {
+    fn set_phase(&mut self, player: Player, phase: PlayerPhase) -> Option<()> {
         self.modify_player(player, |player| player.phase = phase)
     }
-    fn set_buys(self, player: Player, buys: u32) -> Option<BoardState> {
+    fn set_buys(&mut self, player: Player, buys: u32) -> Option<()> {
         self.modify_player(player, |player| player.buys = buys)
     }
-    fn set_actions(self, player: Player, actions: u32) -> Option<BoardState> {
+    fn set_actions(&mut self, player: Player, actions: u32) -> Option<()> {
         self.modify_player(player, |player| player.actions = actions)
     }
-    fn set_gold(self, player: Player, gold: u32) -> Option<BoardState> {
+    fn set_gold(&mut self, player: Player, gold: u32) -> Option<()> {
         self.modify_player(player, |player| player.gold = gold)
     }
-    fn discard_hand(self, player: Player, card: Card) -> Option<BoardState> {
+    fn set_next_hand_size(&mut self, player: Player, size: u32) -> Option<()> {
+        self.modify_player(player, |player| player.next_hand = size)
+    }
+    fn discard_hand(&mut self, player: Player, card: Card) -> Option<()> {
         self.try_modify_player(player, |player| {
-                // try and remove specific card. if it fails try and remove a None
-                if player.hand.remove_item(&Some(card)).is_none() {
-                    player.hand.remove_item(&None)?;
-                }
+                player.take_from_hand(card)?;
                 player.discard.insert(card, 1);
                 Some(())
             }
         )
     }
-    fn discard_played(self, player: Player) -> Option<BoardState> {
+    fn trash_hand(&mut self, player: Player, card: Card) -> Option<()> {
+        self.try_modify_player(player, |player| player.take_from_hand(card))?;
+        self.trash.push(card);
+        Some(())
+    }
+    fn reveal_hand(&mut self, player: Player, cards: Option<CardSet>) -> Option<()> {
+        self.try_modify_player(player, |player| {
+                // Everything revealed must be in the hand, with unknown cards becoming known as needed
+                let learnt = cards.unwrap_or_else(CardSet::empty).difference(&player.hand);
+                player.hand_unknown = player.hand_unknown.checked_sub(learnt.total_count())?;
+                player.hand += &learnt;
+                Some(())
+            }
+        )
+    }
+    fn play_card(&mut self, player: Player, card: Card) -> Option<()> {
+        self.try_modify_player(player, |player| {
+                player.take_from_hand(card)?;
+                player.played.insert(card, 1);
+                Some(())
+            }
+        )
+    }
+    fn set_protected(&mut self, player: Player, protected: bool) -> Option<()> {
+        self.modify_player(player, |player| player.protected = protected)
+    }
+    fn clear_gained(&mut self, player: Player) -> Option<()> {
+        self.modify_player(player, |player| player.gained.clear())
+    }
+    fn concede(&mut self, player: Player) -> Option<()> {
+        if self.conceded.is_some() || self.players.get(player as usize).is_none() {
+            return None;
+        }
+        self.conceded = Some(player);
+        Some(())
+    }
+    fn discard_played(&mut self, player: Player) -> Option<()> {
         self.modify_player(player, |player|
             for card in player.played.drain() {
                 player.discard.insert(card, 1);
             }
         )
     }
-    pub fn mutate(self, m: Mutation) -> Option<BoardState> {
-        match m {
+    pub fn mutate(mut self, m: Mutation) -> Option<BoardState> {
+        self.mutate_in_place(&m)?;
+        Some(self)
+    }
+    /// Apply a mutation directly to the board
+    ///
+    /// This avoids copying the board for every mutation. If the mutation fails the board is left
+    /// unchanged, just as if `mutate` had been given a copy.
+    pub fn mutate_in_place(&mut self, m: &Mutation) -> Option<()> {
+        match *m {
             Mutation::SetPlayers(p) => self.set_players(p),
             Mutation::AddStack(card, count) => self.add_stack(card, count),
             Mutation::GainCard(p, c) => self.gain_card(p, c),
+            Mutation::GainToHand(p, card) => self.gain_to_hand(p, card),
+            Mutation::GainToDeck(p, card) => self.gain_to_deck(p, card),
+            Mutation::ClearGained(p) => self.clear_gained(p),
+            Mutation::SetProtected(p, protected) => self.set_protected(p, protected),
             Mutation::ShuffleDiscard(p) => self.shuffle(p),
+            Mutation::ShuffleInDiscard(p) => self.shuffle_in(p),
             Mutation::DrawCard(p, c) => self.draw_card(p, c),
             Mutation::ChangeTurn(p) => self.change_turn(p),
             Mutation::SetPhase(p, phase) => self.set_phase(p, phase),
             Mutation::SetBuys(p, buys) => self.set_buys(p, buys),
             Mutation::SetActions(p, actions) => self.set_actions(p, actions),
             Mutation::SetGold(p, gold) => self.set_gold(p, gold),
+            Mutation::SetNextHandSize(p, size) => self.set_next_hand_size(p, size),
             Mutation::DiscardHand(p, card) => self.discard_hand(p, card),
             Mutation::DiscardPlayed(p) => self.discard_played(p),
-            _ => unimplemented!("{:?}", m)
+            Mutation::PlayCard(p, card) => self.play_card(p, card),
+            Mutation::TrashHand(p, card) => self.trash_hand(p, card),
+            Mutation::RevealHandCards(p, cards, _) => self.reveal_hand(p, cards),
+            Mutation::Concede(p) => self.concede(p),
+            Mutation::BeginGame => self.num_players().map(|_| ()),
+            // A batch can fail part way through, so it is applied to a copy
+            Mutation::Batch(ref mutations) => {
+                *self = self.clone().mutate_multi(mutations)?;
+                Some(())
+            },
         }
     }
     /// Counts how many of a certain card are presently in the supply
@@ -375,21 +826,388 @@ impl BoardState {
             None
         }
     }
+    /// Supply piles that have had cards taken from them
+    ///
+    /// A pile is touched once its count is below what it held after the starting decks were
+    /// dealt, so Estates and Coppers are only touched once more than the starting decks have
+    /// been taken.
+    pub fn piles_touched(&self) -> Vec<Card> {
+        let players = self.players.len() as u32;
+        self.supply_stacks()
+            .filter(|&(card, count)| {
+                let dealt = card::lists::STARTING_DECK.iter()
+                    .find(|&&(c, _)| c == card)
+                    .map_or(0, |&(_, per_player)| per_player * players);
+                *count + dealt < self.pool.count(card)
+            })
+            .map(|(card, _)| card)
+            .collect()
+    }
+    /// Players that are able to react to the active player playing `card`
+    ///
+    /// Only attacks can be reacted to, and the only reaction is revealing a Moat from hand.
+    pub fn players_with_reaction(&self, card: Card) -> PlayerSet {
+        let mut reactors = PlayerSet::empty();
+        if let Some(players) = self.num_players().filter(|_| card.is_attack()) {
+            for player in PlayerSet::others(self.turn, players).iter(players) {
+                if self.players[player as usize].hand.contains(Card::Moat) {
+                    reactors.insert(player);
+                }
+            }
+        }
+        reactors
+    }
+    /// Every card with a supply pile, in display order
+    ///
+    /// Treasures, victory cards and Curses come first, followed by the kingdom cards sorted by
+    /// cost. Empty piles are included.
+    pub fn supply_cards(&self) -> Vec<Card> {
+        let basics = card::lists::BASE_TREASURE.iter()
+            .chain(card::lists::BASE_VICTORY.iter())
+            .chain([Card::Curse].iter())
+            .cloned()
+            .collect::<Vec<Card>>();
+        let mut kingdom = self.supply_stacks()
+            .map(|(card, _)| card)
+            .filter(|card| !basics.contains(card))
+            .collect::<Vec<Card>>();
+        kingdom.sort_by_key(|card| card.cost());
+        basics.into_iter()
+            .filter(|card| self.stacks.contains(*card))
+            .chain(kingdom)
+            .collect()
+    }
+    /// Cards in non empty supply piles that can be bought with `coins`
+    pub fn affordable(&self, coins: u32) -> impl Iterator<Item = Card> + '_ {
+        self.supply_stacks()
+            .filter(move |(card, count)| **count > 0 && card.cost() <= coins)
+            .map(|(card, _)| card)
+    }
+    /// Cards in non empty supply piles that cost at most `max`, or exactly `max` if `exact`
+    pub fn supply_cards_costing(&self, max: u32, exact: bool) -> Vec<Card> {
+        self.supply_stacks()
+            .filter(|(card, count)| **count > 0 && if exact { card.cost() == max } else { card.cost() <= max })
+            .map(|(card, _)| card)
+            .collect()
+    }
+    /// Render the whole board as a string in a fixed order
+    ///
+    /// Unordered zones are sorted and the RNG is ignored, so equal boards always produce the same
+    /// string. This is intended for golden tests where a readable diff is more useful than
+    /// `PartialEq`.
+    pub fn canonical_string(&self) -> String {
+        let mut out = String::new();
+        let supply = self.supply_stacks()
+            .map(|(card, count)| format!("{:?}={}", card, count))
+            .collect::<Vec<String>>();
+        writeln!(out, "supply: {}", supply.join(" ")).unwrap();
+        writeln!(out, "trash: {}", sorted_card_list(self.trash.iter().map(|c| Some(*c)))).unwrap();
+        writeln!(out, "turn: {:?}", self.turn).unwrap();
+        writeln!(out, "conceded: {:?}", self.conceded).unwrap();
+        for (index, player) in self.players.iter().enumerate() {
+            writeln!(out, "player {}: {:?} actions={} buys={} gold={} shuffles={}",
+                index, player.phase, player.actions, player.buys, player.gold, player.shuffles).unwrap();
+            writeln!(out, "  hand: {}", sorted_card_list(player.hand_iter())).unwrap();
+            writeln!(out, "  draw: {}", card_list(player.draw_iter())).unwrap();
+            writeln!(out, "  discard: {}", sorted_card_list(player.discard_iter().map(Some))).unwrap();
+            writeln!(out, "  played: {}", sorted_card_list(player.played_iter().map(Some))).unwrap();
+            writeln!(out, "  gained: {}", card_list(player.gained_iter().map(Some))).unwrap();
+        }
+        out
+    }
+    /// Rough estimate of how many more turns each player will have
+    ///
+    /// This is a heuristic for AI play and is not a guarantee of anything. It assumes that, across
+    /// all players, a Province is bought about every other turn and ignores every other way the
+    /// game can end.
+    pub fn estimated_turns_remaining(&self) -> u32 {
+        const TURNS_PER_PROVINCE: u32 = 2;
+        let provinces = self.count_supply(Card::Province).unwrap_or(0);
+        let players = self.num_players().map_or(1, |p| p as u32);
+        (provinces * TURNS_PER_PROVINCE).div_ceil(players)
+    }
+    /// Every card owned by `player`, with unknown cards as `None`
+    fn owned_cards(&self, player: Player) -> Option<Vec<Option<Card>>> {
+        let p = self.get_player(player)?;
+        Some(p.hand_iter()
+            .chain(p.draw.iter().cloned())
+            .chain(p.discard.into_iter().chain(p.played).map(Some))
+            .collect())
+    }
+    /// Every known card owned by `player`, along with how many of their cards are unknown
+    ///
+    /// Hand, draw pile, discard and played cards are all merged, so this is the player's deck
+    /// regardless of where its cards currently are.
+    pub fn full_deck(&self, player: Player) -> Option<(CardSet, u32)> {
+        let cards = self.owned_cards(player)?;
+        let unknown = cards.iter().filter(|c| c.is_none()).count() as u32;
+        Some((cards.into_iter().flatten().collect(), unknown))
+    }
+    /// Victory points of the known cards owned by `player`
+    ///
+    /// Unknown cards are ignored, so from a perspective with hidden information this is only a
+    /// lower bound on Estates and the like. Players not in the game have no points.
+    pub fn victory_points(&self, player: Player) -> i32 {
+        self.owned_cards(player)
+            .map_or(0, |cards| cards.iter().filter_map(|c| *c).map(|c| c.victory_points()).sum())
+    }
+    /// Victory points of `player` if every card they own is known
+    pub fn full_victory_points(&self, player: Player) -> Option<i32> {
+        self.owned_cards(player)?
+            .iter()
+            .map(|c| c.map(|c| c.victory_points()))
+            .sum()
+    }
+    /// Players ordered from best to worst
+    ///
+    /// Players are ordered by victory points, with ties going to the player that had fewer turns.
+    /// Players still tied keep their turn order.
+    pub fn rankings(&self) -> Vec<(Player, i32)> {
+        let mut rankings = self.num_players()
+            .map_or(Vec::new(), |players| Player::iter_players(players)
+                .map(|p| (*p, self.victory_points(*p)))
+                .collect());
+        rankings.sort_by_key(|&(p, points)| (-points, self.players[p as usize].turns));
+        rankings
+    }
+    /// Player that is ranked strictly ahead of every other player
+    ///
+    /// `None` if the top players are tied on both victory points and turns.
+    pub fn winner(&self) -> Option<Player> {
+        let rankings = self.rankings();
+        let key = |&(p, points): &(Player, i32)| (points, self.players[p as usize].turns);
+        match (rankings.first(), rankings.get(1)) {
+            (Some(first), Some(second)) if key(first) == key(second) => None,
+            (first, _) => first.map(|&(p, _)| p),
+        }
+    }
+    /// Whether the supply has run down far enough to end the game
+    ///
+    /// The game ends once the Province pile or any three supply piles are empty.
+    pub fn is_game_over(&self) -> bool {
+        self.count_supply(Card::Province) == Some(0)
+            || self.supply_stacks().filter(|(_, count)| **count == 0).count() >= 3
+    }
     /// Perform multiple mutations
     ///
-    /// Only returns a board state if *all* mutations apply successfully
+    /// Only returns a board state if *all* mutations apply successfully. With the
+    /// `debug_invariants` feature the board is checked after every mutation and any violation
+    /// panics, naming the mutation that caused it.
     pub fn mutate_multi(self, mutations: &Mutations) -> Option<BoardState> {
-        let mut state = Some(self);
-        for m in mutations {
-            state = state.and_then(|s| s.mutate(*m));
+        self.try_mutate_multi(mutations).ok()
+    }
+    /// Apply mutations in order, reporting the first that fails
+    pub fn try_mutate_multi(self, mutations: &Mutations) -> Result<BoardState, ApplyError> {
+        let mut state = self;
+        for (index, m) in mutations.iter().enumerate() {
+            state.mutate_in_place(m)
+                .ok_or_else(|| ApplyError { index, mutation: m.clone() })?;
+            #[cfg(feature = "debug_invariants")]
+            {
+                if let Err(err) = state.validate() {
+                    panic!("Invariant violated after {:?}: {}", m, err);
+                }
+            }
+        }
+        Ok(state)
+    }
+    /// Check that the cards on the board could be exactly the `expected` cards
+    ///
+    /// Cards hidden from this board are only counted towards the total, whilst known cards must
+    /// never exceed their expected count.
+    fn check_conserved(&self, expected: &CardSet) -> Result<(), ValidationError> {
+        let mut known = self.supply;
+        let mut unknown = 0;
+        for card in &self.trash {
+            known.insert(*card, 1);
+        }
+        for player in &self.players {
+            for card in player.hand_iter().chain(player.draw.iter().cloned()) {
+                match card {
+                    Some(card) => known.insert(card, 1),
+                    None => unknown += 1,
+                }
+            }
+            for card in player.played.into_iter().chain(player.discard) {
+                known.insert(card, 1);
+            }
+        }
+        if let Some((card, count)) = known.count_iter().find(|(card, count)| **count > expected.count(*card)) {
+            return Err(ValidationError::ExcessCards(card, *count));
+        }
+        let found = known.total_count() + unknown;
+        let expected = expected.total_count();
+        if found != expected {
+            return Err(ValidationError::CardCount { found, expected });
+        }
+        Ok(())
+    }
+    /// Check that only the active player is taking a turn
+    fn check_phases(&self) -> Result<(), ValidationError> {
+        let players = self.num_players().into_iter().flat_map(Player::iter_players);
+        match self.players.iter().zip(players)
+            .find(|(state, player)| **player != self.turn && state.phase != PlayerPhase::NotTurn) {
+            Some((state, player)) => Err(ValidationError::PhaseOutOfTurn(*player, state.phase)),
+            None => Ok(()),
+        }
+    }
+    /// Check the invariants of the board
+    ///
+    /// Every card that entered the game must still be accounted for and only the active player
+    /// may be taking a turn. Boards built by mutations always pass, this is for verifying boards
+    /// that have been edited in some other way.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.check_conserved(&self.pool)?;
+        self.check_phases()
+    }
+    /// Panic unless the cards on the board could be exactly the `expected` cards
+    pub fn assert_conserved(&self, expected: &CardSet) {
+        if let Err(err) = self.check_conserved(expected) {
+            panic!("Cards not conserved: {}", err);
         }
-        state
     }
     pub fn from_mutations(mutations: &Mutations) -> Option<BoardState> {
         Self::new(None).mutate_multi(mutations)
     }
 }
 
+#[cfg(feature = "serde")]
+impl BoardState {
+    /// Snapshot of the board as JSON
+    ///
+    /// The randomness source is not included, see `from_json`.
+    pub fn to_json(&self) -> String {
+        ::serde_json::to_string(self).expect("Boards are always representable as JSON")
+    }
+    /// Board from a snapshot made by `to_json`
+    ///
+    /// The board has no randomness source, so it can be inspected and mutated but any
+    /// `ShuffleDiscard` will fail.
+    pub fn from_json(json: &str) -> Result<BoardState, ::serde_json::Error> {
+        ::serde_json::from_str(json)
+    }
+}
+
+/// Builder for setting up a position without playing to it
+///
+/// Supply piles are added with `Mutation::AddStack`, whilst cards given to players are conjured
+/// into the game. Conjured cards are counted as having entered the game, so the built board
+/// passes `BoardState::validate`.
+#[derive(Debug, Clone, Default)]
+pub struct BoardStateBuilder {
+    seed: Option<RNGSeed>,
+    players: Option<Players>,
+    supply: Vec<(Card, u32)>,
+    discards: Vec<(Player, Card, u32)>,
+    hands: Vec<(Player, Card, u32)>,
+    decks: Vec<(Player, Card, u32)>,
+    active: Option<(Player, PlayerPhase)>,
+}
+
+impl BoardStateBuilder {
+    pub fn players(mut self, players: Players) -> Self {
+        self.players = Some(players);
+        self
+    }
+    pub fn with_seed(mut self, seed: RNGSeed) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    /// Add a supply pile of `count` cards
+    pub fn supply(mut self, card: Card, count: u32) -> Self {
+        self.supply.push((card, count));
+        self
+    }
+    /// Put `count` copies of `card` in the discard of `player`
+    pub fn player_discard(mut self, player: Player, card: Card, count: u32) -> Self {
+        self.discards.push((player, card, count));
+        self
+    }
+    /// Put `count` copies of `card` in the hand of `player`
+    pub fn player_hand(mut self, player: Player, card: Card, count: u32) -> Self {
+        self.hands.push((player, card, count));
+        self
+    }
+    /// Put `count` copies of `card` on top of the deck of `player`
+    ///
+    /// The cards added last are drawn first.
+    pub fn player_deck(mut self, player: Player, card: Card, count: u32) -> Self {
+        self.decks.push((player, card, count));
+        self
+    }
+    /// Make it the turn of `player`, who is in `phase` with one action and one buy
+    ///
+    /// Without this nobody has a turn and `Game::state` is `State::NotStarted`.
+    pub fn active_player(mut self, player: Player, phase: PlayerPhase) -> Self {
+        self.active = Some((player, phase));
+        self
+    }
+    /// Board with everything that was added
+    ///
+    /// Returns `None` if a supply pile is added twice, cards are given to or the turn is given
+    /// to a player that is not in the game, or the board does not pass `BoardState::validate`.
+    pub fn build(self) -> Option<BoardState> {
+        let mut bs = BoardState::new(self.seed);
+        if let Some(players) = self.players {
+            bs = bs.mutate(Mutation::SetPlayers(players))?;
+        }
+        if let Some((player, phase)) = self.active {
+            bs = bs.mutate_multi(&vec![
+                Mutation::ChangeTurn(player),
+                Mutation::SetPhase(player, phase),
+                Mutation::SetBuys(player, 1),
+                Mutation::SetActions(player, 1),
+            ])?;
+        }
+        for (card, count) in self.supply {
+            bs = bs.mutate(Mutation::AddStack(card, count))?;
+        }
+        for (player, card, count) in self.discards {
+            bs.players.get_mut(player as usize)?.discard.insert(card, count);
+            bs.pool.insert(card, count);
+        }
+        for (player, card, count) in self.hands {
+            bs.players.get_mut(player as usize)?.hand.insert(card, count);
+            bs.pool.insert(card, count);
+        }
+        for (player, card, count) in self.decks {
+            let draw = &mut bs.players.get_mut(player as usize)?.draw;
+            draw.extend((0..count).map(|_| Some(card)));
+            bs.pool.insert(card, count);
+        }
+        bs.validate().ok()?;
+        Some(bs)
+    }
+}
+
+#[cfg(test)]
+impl BoardState {
+    /// Swap the `removed` cards for `added` in the cards that entered the game
+    ///
+    /// Keeps the board valid when a test conjures cards, as `debug_invariants` checks it.
+    fn replace_in_pool<I: Iterator<Item = Card>>(&mut self, removed: CardSet, added: I) {
+        self.pool -= &removed;
+        self.pool += &added.collect();
+    }
+    /// Replace the draw pile of a player with a known order
+    ///
+    /// The first card of `cards` is the top of the deck. This bypasses shuffling entirely so that
+    /// effects depending on the order of the deck can be tested against a fixed sequence.
+    pub fn stack_deck(&mut self, player: Player, cards: &[Card]) {
+        let removed = self.players[player as usize].draw.iter().flatten().cloned().collect();
+        self.replace_in_pool(removed, cards.iter().cloned());
+        self.players[player as usize].draw = cards.iter().rev().map(|c| Some(*c)).collect();
+    }
+    /// Replace the hand of a player with known cards
+    pub fn set_hand(&mut self, player: Player, cards: &[Card]) {
+        let removed = self.players[player as usize].hand;
+        self.replace_in_pool(removed, cards.iter().cloned());
+        let player = &mut self.players[player as usize];
+        player.hand = cards.iter().cloned().collect();
+        player.hand_unknown = 0;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -444,6 +1262,21 @@ mod tests {
         assert_ne!(bs.players[0].draw, cards);
     }
     #[test]
+    fn shuffles_counted() {
+        let mut bs = two_player_with_stacks();
+        assert_eq!(bs.players[0].get_shuffle_count(), 0);
+        bs = bs.mutate(Mutation::ShuffleDiscard(Player::P0)).unwrap();
+        bs.players[0].draw = Vec::new();
+        bs.players[0].discard.insert(Card::Copper, 1);
+        bs = bs.mutate(Mutation::ShuffleDiscard(Player::P0)).unwrap();
+        assert_eq!(bs.clone().players[0].get_shuffle_count(), 2);
+        // Nothing to shuffle
+        bs.players[0].draw = Vec::new();
+        bs = bs.mutate(Mutation::ShuffleDiscard(Player::P0)).unwrap();
+        assert_eq!(bs.players[0].get_shuffle_count(), 2);
+        assert_eq!(bs.players[1].get_shuffle_count(), 0);
+    }
+    #[test]
     fn shuffle_stable() {
         let mut bs1 = two_player_with_stacks();
         let mut bs2 = two_player_with_stacks();
@@ -459,8 +1292,390 @@ mod tests {
         assert_ne!(bs.clone().mutate(Mutation::DrawCard(Player::P0, Some(Card::Copper))), None);
         // Attempt to turn it into a none. Should succeed with information unchanged
         let bs1 = bs.clone().mutate(Mutation::DrawCard(Player::P0, None)).unwrap();
-        assert_eq!(bs1.players[0].hand[0], Some(Card::Copper));
+        assert_eq!(bs1.players[0].hand_iter().collect::<Vec<Option<Card>>>(), vec![Some(Card::Copper)]);
         // Should not be able to change cards though
         assert_eq!(bs.mutate(Mutation::DrawCard(Player::P0, Some(Card::Gold))), None);
     }
+    #[test]
+    fn stacked_deck_draws_in_order() {
+        let mut bs = two_player_with_stacks();
+        bs.stack_deck(Player::P0, &[Card::Gold, Card::Village, Card::Silver]);
+        assert_eq!(bs.players[0].draw_iter().collect::<Vec<Option<Card>>>(),
+            vec![Some(Card::Gold), Some(Card::Village), Some(Card::Silver)]);
+        for card in &[Card::Gold, Card::Village, Card::Silver] {
+            assert!(!bs.players[0].hand.contains(*card));
+            bs = bs.mutate(Mutation::DrawCard(Player::P0, None)).unwrap();
+            assert!(bs.players[0].hand.contains(*card));
+        }
+    }
+    #[test]
+    fn turns_remaining_follows_provinces() {
+        let two = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Two)).unwrap();
+        let three = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Three)).unwrap();
+        assert_eq!(two.clone().mutate(Mutation::AddStack(Card::Province, 8)).unwrap().estimated_turns_remaining(), 8);
+        assert_eq!(three.mutate(Mutation::AddStack(Card::Province, 12)).unwrap().estimated_turns_remaining(), 8);
+        assert_eq!(two.mutate(Mutation::AddStack(Card::Province, 1)).unwrap().estimated_turns_remaining(), 1);
+    }
+    #[test]
+    fn batch_is_atomic() {
+        let bs = BoardState::new(None).mutate(Mutation::Batch(vec![
+            Mutation::SetPlayers(Players::Two),
+            Mutation::AddStack(Card::Copper, 10),
+            Mutation::GainCard(Player::P0, Card::Copper),
+        ])).unwrap();
+        assert_eq!(bs.count_supply(Card::Copper), Some(9));
+        assert_eq!(bs.players[0].discard.count(Card::Copper), 1);
+        // The second stack of Copper fails, so the Silver stack should not be added either
+        let failed = Mutation::Batch(vec![
+            Mutation::AddStack(Card::Silver, 10),
+            Mutation::AddStack(Card::Copper, 10),
+        ]);
+        assert_eq!(bs.clone().mutate(failed.clone()), None);
+        assert_eq!(bs.count_supply(Card::Silver), None);
+        // Nested batches are applied as a whole as well
+        assert_eq!(bs.mutate(Mutation::Batch(vec![Mutation::AddStack(Card::Gold, 10), failed])), None);
+    }
+    #[test]
+    fn cards_conserved() {
+        let bs = BoardState::new(None).mutate_multi(&vec![
+            Mutation::SetPlayers(Players::Two),
+            Mutation::AddStack(Card::Copper, 10),
+            Mutation::GainCard(Player::P0, Card::Copper),
+            Mutation::ShuffleDiscard(Player::P0),
+        ]).unwrap();
+        bs.assert_conserved(&bs.pool);
+        let mut broken = bs.clone();
+        broken.players[1].discard.insert(Card::Copper, 1);
+        // The shuffled deck is hidden, so this could only be detected from the total
+        assert_eq!(broken.validate(), Err(ValidationError::CardCount { found: 11, expected: 10 }));
+        let mut broken = bs.clone();
+        broken.supply.insert(Card::Copper, 2);
+        assert_eq!(broken.validate(), Err(ValidationError::ExcessCards(Card::Copper, 11)));
+    }
+    #[test]
+    fn only_active_player_in_turn() {
+        let mut bs = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Three)).unwrap();
+        assert_eq!(bs.validate(), Ok(()));
+        bs = bs.mutate(Mutation::SetPhase(Player::P0, PlayerPhase::Action)).unwrap();
+        assert_eq!(bs.validate(), Ok(()));
+        bs = bs.mutate(Mutation::SetPhase(Player::P2, PlayerPhase::Buy)).unwrap();
+        assert_eq!(bs.validate(), Err(ValidationError::PhaseOutOfTurn(Player::P2, PlayerPhase::Buy)));
+        // Every seat of a large game is checked
+        let mut bs = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Six)).unwrap();
+        bs = bs.mutate(Mutation::SetPhase(Player::P5, PlayerPhase::Action)).unwrap();
+        assert_eq!(bs.validate(), Err(ValidationError::PhaseOutOfTurn(Player::P5, PlayerPhase::Action)));
+    }
+    #[cfg(feature = "debug_invariants")]
+    #[test]
+    #[should_panic(expected = "Invariant violated after GainCard(P1, Copper): 11 copies of Copper")]
+    fn duplicated_card_trips_invariants() {
+        let mut bs = BoardState::new(None).mutate_multi(&vec![
+            Mutation::SetPlayers(Players::Two),
+            Mutation::AddStack(Card::Copper, 10),
+        ]).unwrap();
+        // Conjure a card out of nowhere
+        bs.players[0].discard.insert(Card::Copper, 1);
+        bs.mutate_multi(&vec![Mutation::GainCard(Player::P1, Card::Copper)]);
+    }
+    #[test]
+    fn only_moat_holders_react() {
+        let mut bs = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Three)).unwrap();
+        bs.players[0].hand.insert(Card::Moat, 1);
+        bs.players[1].hand.insert(Card::Moat, 1);
+        bs.players[2].hand.insert(Card::Copper, 1);
+        bs.players[2].hand_unknown = 1;
+        let reactors = bs.players_with_reaction(Card::Militia);
+        // P0 is the attacker and so cannot react even though it has a Moat
+        assert_eq!(reactors, PlayerSet::just(Player::P1));
+        assert_eq!(bs.players_with_reaction(Card::Smithy), PlayerSet::empty());
+    }
+    #[test]
+    fn hand_size_includes_unknowns() {
+        let mut bs = two_player_with_stacks();
+        bs.players[1].hand.insert(Card::Copper, 1);
+        bs.players[1].hand.insert(Card::Estate, 1);
+        bs.players[1].hand_unknown = 3;
+        assert_eq!(bs.players[1].hand_size(), 5);
+        let known = bs.players[1].known_hand();
+        assert_eq!(known.total_count(), 2);
+        assert_eq!(known.count(Card::Copper), 1);
+        assert_eq!(known.count(Card::Estate), 1);
+    }
+    #[test]
+    fn player_set_members() {
+        let mut set = PlayerSet::just(Player::P0);
+        set.insert(Player::P2);
+        assert_eq!(set.iter(Players::Four).collect::<Vec<Player>>(), vec![Player::P0, Player::P2]);
+        // Members outside of the game are not iterated
+        assert_eq!(set.iter(Players::Two).collect::<Vec<Player>>(), vec![Player::P0]);
+        let others = PlayerSet::others(Player::P0, Players::Three);
+        assert_eq!(others.iter(Players::Three).collect::<Vec<Player>>(), vec![Player::P1, Player::P2]);
+        assert!(!others.contains(Player::P0));
+        assert_eq!(PlayerSet::all(Players::Three).iter(Players::Four).count(), 3);
+        // Turn order wraps around from the first player
+        let others = PlayerSet::others(Player::P2, Players::Four);
+        assert_eq!(others.iter_from(Player::P3, Players::Four).collect::<Vec<Player>>(),
+            vec![Player::P3, Player::P0, Player::P1]);
+    }
+    #[test]
+    fn reveal_upgrades_unknown_cards() {
+        let mut bs = two_player_with_stacks();
+        bs.players[1].hand.insert(Card::Copper, 1);
+        bs.players[1].hand_unknown = 2;
+        let mut cards = CardSet::empty();
+        cards.insert(Card::Copper, 2);
+        bs = bs.mutate(Mutation::RevealHandCards(Player::P1, Some(cards), Reveal::All)).unwrap();
+        assert_eq!(bs.players[1].hand_iter().collect::<Vec<Option<Card>>>(), vec![Some(Card::Copper), Some(Card::Copper), None]);
+        // Cannot reveal more than is in the hand
+        cards.insert(Card::Gold, 2);
+        assert_eq!(bs.clone().mutate(Mutation::RevealHandCards(Player::P1, Some(cards), Reveal::All)), None);
+        // Nothing is learnt from a reveal of unknown cards
+        let unchanged = bs.clone().mutate(Mutation::RevealHandCards(Player::P1, None, Reveal::All)).unwrap();
+        assert_eq!(unchanged.players[1].hand, bs.players[1].hand);
+    }
+    #[test]
+    fn discard_known_and_hidden_cards() {
+        let mut bs = two_player_with_stacks();
+        bs.players[1].hand.insert(Card::Copper, 1);
+        bs.players[1].hand_unknown = 1;
+        // A known card is discarded from the known cards
+        let known = bs.clone().mutate(Mutation::DiscardHand(Player::P1, Card::Copper)).unwrap();
+        assert_eq!(known.players[1].hand_iter().collect::<Vec<Option<Card>>>(), vec![None]);
+        // Otherwise the card must have been one of the unknown cards
+        let hidden = bs.clone().mutate(Mutation::DiscardHand(Player::P1, Card::Estate)).unwrap();
+        assert_eq!(hidden.players[1].hand_iter().collect::<Vec<Option<Card>>>(), vec![Some(Card::Copper)]);
+        assert_eq!(hidden.players[1].discard.count(Card::Estate), 1);
+        let hidden = hidden.mutate(Mutation::DiscardHand(Player::P1, Card::Copper)).unwrap();
+        assert_eq!(hidden.players[1].hand_size(), 0);
+        assert_eq!(hidden.mutate(Mutation::DiscardHand(Player::P1, Card::Copper)), None);
+    }
+    #[test]
+    fn hand_order_does_not_matter() {
+        let mut a = two_player_with_stacks();
+        a.players[1].draw = vec![Some(Card::Copper), Some(Card::Estate)];
+        let mut b = a.clone();
+        b.players[1].draw.reverse();
+        let draw = |bs: BoardState| bs.mutate(Mutation::DrawCard(Player::P1, None))
+            .and_then(|bs| bs.mutate(Mutation::DrawCard(Player::P1, None)));
+        assert!(draw(a.clone()).is_some());
+        assert_eq!(draw(a), draw(b));
+    }
+    #[test]
+    fn trash_from_hand() {
+        let mut bs = two_player_with_stacks();
+        bs.players[1].hand.insert(Card::Estate, 1);
+        bs.players[1].hand_unknown = 1;
+        bs = bs.mutate(Mutation::TrashHand(Player::P1, Card::Estate)).unwrap();
+        assert_eq!(bs.players[1].hand_iter().collect::<Vec<Option<Card>>>(), vec![None]);
+        // The unknown card is trashed if the card is not known to be in hand
+        bs = bs.mutate(Mutation::TrashHand(Player::P1, Card::Copper)).unwrap();
+        assert_eq!(bs.players[1].hand_size(), 0);
+        assert_eq!(bs.trash_iter().collect::<Vec<Card>>(), vec![Card::Estate, Card::Copper]);
+        assert_eq!(bs.count_trash(Card::Estate), 1);
+        assert_eq!(bs.count_trash(Card::Gold), 0);
+        assert_eq!(bs.mutate(Mutation::TrashHand(Player::P1, Card::Copper)), None);
+    }
+    #[test]
+    fn gain_into_hand() {
+        let mut bs = two_player_with_stacks().mutate(Mutation::AddStack(Card::Silver, 1)).unwrap();
+        bs = bs.mutate(Mutation::GainToHand(Player::P1, Card::Silver)).unwrap();
+        assert_eq!(bs.players[1].hand_iter().collect::<Vec<Option<Card>>>(), vec![Some(Card::Silver)]);
+        assert_eq!(bs.players[1].gained_iter().collect::<Vec<Card>>(), vec![Card::Silver]);
+        assert_eq!(bs.count_supply(Card::Silver), Some(0));
+        assert_eq!(bs.mutate(Mutation::GainToHand(Player::P1, Card::Silver)), None);
+    }
+    #[test]
+    fn gain_onto_deck() {
+        let mut bs = two_player_with_stacks()
+            .mutate(Mutation::ShuffleDiscard(Player::P0)).unwrap()
+            .mutate(Mutation::AddStack(Card::Silver, 1)).unwrap();
+        bs = bs.mutate(Mutation::GainToDeck(Player::P0, Card::Silver)).unwrap();
+        assert_eq!(bs.players[0].draw_iter().next(), Some(Some(Card::Silver)));
+        assert_eq!(bs.players[0].draw_iter().count(), 9 + 3 + 42 + 1);
+        assert_eq!(bs.players[0].gained_iter().collect::<Vec<Card>>(), vec![Card::Silver]);
+        assert_eq!(bs.count_supply(Card::Silver), Some(0));
+        assert_eq!(bs.clone().mutate(Mutation::GainToDeck(Player::P0, Card::Silver)), None);
+        bs = bs.mutate(Mutation::DrawCard(Player::P0, None)).unwrap();
+        assert_eq!(bs.players[0].hand_iter().collect::<Vec<Option<Card>>>(), vec![Some(Card::Silver)]);
+    }
+    #[test]
+    fn game_over_from_piles() {
+        let mut bs = two_player_with_stacks();
+        for card in &[Card::Province, Card::Village, Card::Smithy, Card::Moat] {
+            bs = bs.mutate(Mutation::AddStack(*card, 1)).unwrap();
+        }
+        assert!(!bs.is_game_over());
+        let provinces = bs.clone().mutate(Mutation::GainCard(Player::P0, Card::Province)).unwrap();
+        assert!(provinces.is_game_over());
+        bs = bs.mutate(Mutation::GainCard(Player::P0, Card::Village)).unwrap();
+        bs = bs.mutate(Mutation::GainCard(Player::P1, Card::Smithy)).unwrap();
+        assert!(!bs.is_game_over());
+        bs = bs.mutate(Mutation::GainCard(Player::P0, Card::Moat)).unwrap();
+        assert!(bs.is_game_over());
+    }
+    #[test]
+    fn victory_points_of_all_zones() {
+        let mut bs = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Two)).unwrap();
+        bs.players[0].hand.insert(Card::Estate, 1);
+        bs.players[0].draw = vec![Some(Card::Duchy), Some(Card::Copper)];
+        bs.players[0].discard.insert(Card::Curse, 2);
+        bs.players[0].played.insert(Card::Province, 1);
+        assert_eq!(bs.victory_points(Player::P0), 1 + 3 - 2 + 6);
+        assert_eq!(bs.full_victory_points(Player::P0), Some(8));
+        assert_eq!(bs.victory_points(Player::P1), 0);
+        // Only curses leaves a negative score
+        bs.players[1].discard.insert(Card::Curse, 3);
+        assert_eq!(bs.full_victory_points(Player::P1), Some(-3));
+        // Hidden cards could be worth anything
+        bs.players[0].draw.push(None);
+        bs.players[0].hand_unknown = 1;
+        assert_eq!(bs.victory_points(Player::P0), 8);
+        assert_eq!(bs.full_victory_points(Player::P0), None);
+    }
+    #[test]
+    fn rankings_break_ties_on_turns() {
+        let mut bs = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Three)).unwrap();
+        bs.players[0].discard.insert(Card::Province, 1);
+        bs.players[1].discard.insert(Card::Duchy, 2);
+        bs.players[2].discard.insert(Card::Estate, 3);
+        assert_eq!(bs.rankings(), vec![(Player::P0, 6), (Player::P1, 6), (Player::P2, 3)]);
+        assert_eq!(bs.winner(), None);
+        for p in &[Player::P0, Player::P1, Player::P2, Player::P0] {
+            bs = bs.mutate(Mutation::ChangeTurn(*p)).unwrap();
+        }
+        // P1 reached the same points in fewer turns
+        assert_eq!(bs.rankings(), vec![(Player::P1, 6), (Player::P0, 6), (Player::P2, 3)]);
+        assert_eq!(bs.winner(), Some(Player::P1));
+        bs.players[2].discard.insert(Card::Province, 1);
+        assert_eq!(bs.winner(), Some(Player::P2));
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn board_round_trips_json() {
+        let (g, _) = ::Game::new_from_seed(::Rules::new(Players::Two, card::lists::FIRST_SET), ::tests::DUMMY_SEED);
+        let json = g.board_state().to_json();
+        let bs = BoardState::from_json(&json).unwrap();
+        assert_eq!(&bs, g.board_state());
+        assert_eq!(bs.turn_number(), g.board_state().turn_number());
+        assert_eq!(bs.get_player(Player::P0).unwrap().hand_size(), 5);
+        assert!(bs.mutate(Mutation::ShuffleDiscard(Player::P1)).is_none());
+        assert!(BoardState::from_json("{}").is_err());
+    }
+    #[test]
+    fn six_players() {
+        let bs = BoardState::new(None).mutate(Mutation::SetPlayers(Players::Six)).unwrap();
+        assert_eq!(bs.num_players(), Some(Players::Six));
+        assert!(bs.get_player(Player::P5).is_some());
+        assert_eq!(Player::P4.next(Players::Six), Player::P5);
+        assert_eq!(Player::P5.next(Players::Six), Player::P0);
+        assert_eq!(Player::P4.next(Players::Five), Player::P0);
+        assert_eq!(PlayerSet::others(Player::P5, Players::Six).iter(Players::Six).count(), 5);
+    }
+    #[test]
+    fn builder_matches_manual_setup() {
+        let bs = BoardState::builder()
+            .with_seed(::tests::DUMMY_SEED)
+            .players(Players::Two)
+            .player_discard(Player::P0, Card::Copper, 9)
+            .player_discard(Player::P0, Card::Silver, 3)
+            .player_discard(Player::P0, Card::Gold, 42)
+            .build()
+            .unwrap();
+        assert_eq!(bs, two_player_with_stacks());
+        assert_eq!(bs.seed(), Some(::tests::DUMMY_SEED));
+        assert_eq!(bs.validate(), Ok(()));
+        // Shuffles match as the seed is the same
+        assert_eq!(bs.mutate(Mutation::ShuffleDiscard(Player::P0)),
+            two_player_with_stacks().mutate(Mutation::ShuffleDiscard(Player::P0)));
+        let supplied = BoardState::builder()
+            .players(Players::Three)
+            .supply(Card::Village, 10)
+            .player_hand(Player::P2, Card::Moat, 1)
+            .build()
+            .unwrap();
+        assert_eq!(supplied.count_supply(Card::Village), Some(10));
+        assert!(supplied.get_player(Player::P2).unwrap().known_hand().contains(Card::Moat));
+        assert_eq!(supplied.validate(), Ok(()));
+        assert_eq!(BoardState::builder().supply(Card::Gold, 1).supply(Card::Gold, 2).build(), None);
+        assert_eq!(BoardState::builder().players(Players::Two).player_hand(Player::P2, Card::Moat, 1).build(), None);
+    }
+    #[test]
+    fn repeated_inspection() {
+        // Rollouts inspect hands and decks far more often than they change, so the iterators
+        // borrow the player rather than copying their cards
+        let mut bs = two_player_with_stacks().mutate(Mutation::ShuffleDiscard(Player::P0)).unwrap();
+        for _ in 0..5 {
+            bs = bs.mutate(Mutation::DrawCard(Player::P0, None)).unwrap();
+        }
+        let player = bs.get_player(Player::P0).unwrap();
+        let mut seen = 0;
+        for _ in 0..10_000 {
+            seen += player.hand_iter().filter(|c| c.is_some()).count();
+            seen += player.draw_iter().filter(|c| c.is_some()).count();
+        }
+        assert_eq!(seen, 10_000 * (9 + 3 + 42));
+    }
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = ::std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+    #[test]
+    fn equal_boards_hash_equal() {
+        let seeded = two_player_with_stacks();
+        let unseeded = BoardState::builder()
+            .players(Players::Two)
+            .player_discard(Player::P0, Card::Gold, 42)
+            .player_discard(Player::P0, Card::Silver, 3)
+            .player_discard(Player::P0, Card::Copper, 9)
+            .build()
+            .unwrap()
+            .mutate(Mutation::ChangeTurn(Player::P1))
+            .unwrap();
+        assert_eq!(seeded, unseeded);
+        assert_eq!(hash_of(&seeded), hash_of(&unseeded));
+        // Phases and other turn state are not compared or hashed
+        let phased = seeded.clone().mutate(Mutation::SetPhase(Player::P0, PlayerPhase::Buy)).unwrap();
+        assert_eq!(hash_of(&phased), hash_of(&seeded));
+        let shuffled = seeded.clone().mutate(Mutation::ShuffleDiscard(Player::P0)).unwrap();
+        assert_ne!(shuffled, seeded);
+        assert_ne!(hash_of(&shuffled), hash_of(&seeded));
+    }
+    #[test]
+    fn shuffle_in_keeps_deck_on_top() {
+        let mut bs = two_player_with_stacks();
+        bs.players[0].draw = vec![Some(Card::Estate), Some(Card::Duchy)];
+        assert_eq!(bs.clone().mutate(Mutation::ShuffleDiscard(Player::P0)), None);
+        let shuffled = bs.clone().mutate(Mutation::ShuffleInDiscard(Player::P0)).unwrap();
+        let deck: Vec<Option<Card>> = shuffled.players[0].draw_iter().collect();
+        assert_eq!(deck.len(), 2 + 9 + 3 + 42);
+        assert_eq!(&deck[..2], &[Some(Card::Duchy), Some(Card::Estate)]);
+        assert!(shuffled.players[0].discard.is_empty());
+        assert_eq!(shuffled.players[0].get_shuffle_count(), 1);
+        // The same seed shuffles the same way
+        let again = bs.mutate(Mutation::ShuffleInDiscard(Player::P0)).unwrap();
+        assert_eq!(again.players[0].draw, shuffled.players[0].draw);
+    }
+    #[test]
+    fn display_small_board() {
+        let mut bs = BoardState::builder()
+            .players(Players::Two)
+            .supply(Card::Copper, 3)
+            .supply(Card::Province, 0)
+            .supply(Card::Village, 10)
+            .player_hand(Player::P1, Card::Copper, 2)
+            .build()
+            .unwrap()
+            .mutate(Mutation::ChangeTurn(Player::P1)).unwrap()
+            .mutate(Mutation::SetBuys(Player::P1, 1)).unwrap()
+            .mutate(Mutation::SetGold(Player::P1, 2)).unwrap()
+            .mutate(Mutation::TrashHand(Player::P1, Card::Copper)).unwrap();
+        bs.players[1].hand_unknown = 2;
+        bs.players[1].draw = vec![None, Some(Card::Estate), None, None];
+        bs.players[1].played.insert(Card::Village, 1);
+        bs.players[1].discard.insert(Card::Estate, 3);
+        assert_eq!(bs.to_string(), "Supply:\n\tCopper: 3\n\tProvince: DEPLETED\n\tVillage: 10\nTrash:\n\tCopper\nIt is player 2's turn\n");
+        assert_eq!(bs.players[1].to_string(), "Hand: [Copper, 2 unknown]\nPlayed: [Village]\nDeck: [2 unknown, Estate, 1 unknown]\n\
+            Discard: 3 cards\nActions: 0 Buys: 1 Gold: 2\n");
+    }
 }
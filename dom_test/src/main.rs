@@ -1,155 +1,120 @@
 extern crate dom_core;
 
-use std::{io, fmt};
-use dom_core::card;
+use std::io;
 
-struct SupplyCard {
-    card: dom_core::Card,
-    quantity: u32,
-}
-
-impl<'a> From<(dom_core::Card, &'a u32)> for SupplyCard {
-    fn from(sup: (dom_core::Card, &'a u32)) -> SupplyCard {
-        SupplyCard { card: sup.0, quantity: *sup.1}
+fn make_action(game: &dom_core::Game, input: &str) -> Option<dom_core::Action> {
+    if input == "buy" && game.state() == dom_core::State::ActionPhase {
+        return Some(dom_core::Action::EndAction);
     }
-}
-
-impl fmt::Display for SupplyCard {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}: {}", self.card, if self.quantity == 0 { format!("DEPLETED")} else { format!("{}", self.quantity)})
+    if input == "turn" {
+        return Some(dom_core::Action::EndBuy);
     }
-}
-
-fn print_supply_cards(state: &dom_core::BoardState, cards: &[dom_core::Card]) {
-    for kv in state.supply_stacks().filter(|(key, _)| cards.contains(key)) {
-        println!("\t{}", SupplyCard::from(kv));
+    if input == "concede" {
+        return Some(dom_core::Action::Concede);
     }
-}
-
-fn is_base_card(card: dom_core::Card) -> bool {
-    card::lists::BASE_TREASURE.contains(&card) ||
-        card::lists::BASE_VICTORY.contains(&card) ||
-        card == dom_core::Card::Curse
-}
-
-fn print_board_state(state: &dom_core::BoardState) {
-    println!("Supply:");
-    print_supply_cards(state, &card::lists::BASE_TREASURE);
-    print_supply_cards(state, &card::lists::BASE_VICTORY);
-    print_supply_cards(state, &[dom_core::Card::Curse]);
-    let v: Vec<dom_core::Card> = state.supply_stacks().map(|(key, v)| key).filter(|key| !is_base_card(*key)).collect();
-    print_supply_cards(state, &v);
-    println!("Trash:");
-    println!("\tNOT DISPLAYED");
-    println!("It is player {}'s turn", (state.active_player() as u32) + 1);
-}
-
-#[derive(Debug, Clone, Copy)]
-enum MaybeCardRange {
-    Known(dom_core::Card),
-    Unknown(u32),
-}
-
-impl fmt::Display for MaybeCardRange {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            MaybeCardRange::Known(card) => write!(f, "{}", format!("{:?}", card)),
-            MaybeCardRange::Unknown(count) => write!(f, "{} unknown", format!("{}", count)),
-        }
+    if input == "treasures" {
+        return Some(dom_core::Action::PlayAllTreasures);
     }
-}
-
-struct MaybeCardList {
-    ranges: Vec<MaybeCardRange>,
-}
-
-impl<T: Iterator<Item = Option<dom_core::Card>> + Sized> From<T> for MaybeCardList {
-    fn from(iter: T) -> Self {
-        MaybeCardList {
-            ranges:
-                iter.fold(Vec::new(), |acc_old, x|
-                    {
-                        let mut acc = acc_old;
-                        if let Some(card) = x {
-                            acc.push(MaybeCardRange::Known(card))
-                        } else {
-                            match acc.pop() {
-                                Some(old@MaybeCardRange::Known(_)) => {
-                                    acc.push(old);
-                                    acc.push(MaybeCardRange::Unknown(1))
-                                },
-                                Some(MaybeCardRange::Unknown(count)) =>
-                                    acc.push(MaybeCardRange::Unknown(count + 1)),
-                                None =>
-                                    acc.push(MaybeCardRange::Unknown(1)),
-                            }
-                        }
-                        acc
-                    }
-                ),
-        }
+    let mut words = input.splitn(2, ' ');
+    let (verb, name) = (words.next()?, words.next()?);
+    let card = name.parse::<dom_core::Card>().ok()?;
+    match verb {
+        "buy" => Some(dom_core::Action::Buy(card)),
+        "play" if card.is_treasure() => Some(dom_core::Action::PlayTreasure(card)),
+        "play" => Some(dom_core::Action::PlayAction(card)),
+        _ => None,
     }
 }
 
-impl fmt::Display for MaybeCardList {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.ranges.iter()
-            .map(|x| format!("{}", x))
-            // Now turn into a single joined string
-            .collect::<Vec<String>>().join(", ")
-        )
+/// Parse the answer to `decision` typed after "choose", card names or "yes"/"no" to react
+fn make_choice(decision: dom_core::Decision, answer: &str) -> Option<dom_core::Choice> {
+    use dom_core::{Choice, Decision};
+    if let Decision::ReactToAttack { .. } = decision {
+        return match answer.trim() {
+            "yes" => Some(Choice::Bool(true)),
+            "no" => Some(Choice::Bool(false)),
+            _ => None,
+        };
+    }
+    let cards = answer.split_whitespace()
+        .map(|name| name.parse::<dom_core::Card>().ok())
+        .collect::<Option<Vec<dom_core::Card>>>()?;
+    match decision {
+        Decision::CellarDiscard { .. } | Decision::TrashUpTo { .. } | Decision::MilitiaDiscard { .. } =>
+            Some(Choice::Cards(cards)),
+        Decision::MineUpgrade { .. } if !cards.is_empty() => Some(Choice::Cards(cards)),
+        _ => match cards.as_slice() {
+            [] => Some(Choice::None),
+            [card] => Some(Choice::Card(*card)),
+            _ => None,
+        },
     }
 }
 
-fn show_player(player: &dom_core::PlayerState) {
-    println!("Hand: [{}]", MaybeCardList::from(player.hand_iter()));
-    let played_vec: Vec<dom_core::Card> = player.played_iter().collect();
-    println!("Played: {:?}", played_vec);
-    println!("Deck: [{}]", MaybeCardList::from(player.draw_iter()));
-    println!("Discard: NOT DISPLAYED");
-    println!("Actions: {} Buys: {} Gold: {}", player.get_actions(), player.get_buys(), player.get_gold());
+/// Something typed at the prompt
+enum Request {
+    Act(dom_core::Action),
+    Resolve(dom_core::Decision, dom_core::Choice),
 }
 
-fn mutations_for_player(mutations: dom_core::Mutations, player: dom_core::Player) -> dom_core::Mutations {
-    mutations.into_iter().map(|x|
-        match x {
-            dom_core::Mutation::RevealHandCards(p, s, r) => unimplemented!(),
-            dom_core::Mutation::DrawCard(p, c) if p != player => dom_core::Mutation::DrawCard(p, None),
-            other => other
-        }
-    ).collect()
+fn make_request(game: &dom_core::Game, input: &str) -> Option<Request> {
+    if let (Some(decision), true) = (game.pending_decision(), input.starts_with("choose")) {
+        return make_choice(decision, &input["choose".len()..]).map(|choice| Request::Resolve(decision, choice));
+    }
+    make_action(game, input).map(Request::Act)
 }
 
-fn make_action(game: &dom_core::Game, input: &str) -> Option<dom_core::Action> {
-    if input == "buy" && game.state() == dom_core::State::ActionPhase {
-        return Some(dom_core::Action::EndAction);
+/// Explain to the person at the keyboard why `action` was refused
+fn describe_refusal(action: dom_core::Action, err: dom_core::ActionError) -> String {
+    use dom_core::{Action, ActionError, State};
+    match (action, err) {
+        (Action::Buy(card), ActionError::NotEnoughGold { cost, gold }) =>
+            format!("not enough gold for {}, it costs {} and you have {}", card, cost, gold),
+        (_, ActionError::NotEnoughGold { cost, gold }) =>
+            format!("not enough gold, it costs {} and you have {}", cost, gold),
+        (_, ActionError::WrongState(State::ActionPhase)) =>
+            "it's your action phase, type 'buy' to move on to buying".to_string(),
+        (_, ActionError::WrongState(State::BuyPhase)) => "it's not your action phase".to_string(),
+        (_, ActionError::WrongState(State::PendingDecision)) => "a decision has to be made first".to_string(),
+        (_, ActionError::WrongState(State::GameOver)) => "the game is over".to_string(),
+        (_, ActionError::WrongState(State::NotStarted)) => "nobody has a turn yet".to_string(),
+        (Action::PlayTreasure(card), ActionError::CannotPlay(_)) => format!("{} is not a treasure", card),
+        (_, ActionError::CannotPlay(card)) => format!("{} can't be played yet", card),
+        (_, ActionError::NotInHand(card)) => format!("you have no {} in hand", card),
+        (_, ActionError::NoActions) => "you have no actions left".to_string(),
+        (_, ActionError::NoBuys) => "you have no buys left".to_string(),
+        (_, ActionError::NotInSupply(card)) => format!("{} is not in the supply", card),
+        (_, ActionError::PileEmpty(card)) => format!("there are no {} left", card),
+        (_, ActionError::Rejected) => "the game rejected that".to_string(),
     }
-    if input == "turn" {
-        return Some(dom_core::Action::EndBuy);
-    }
-    None
 }
 
 fn main() {
     let (mut game, mutations) = dom_core::Game::new_first_game(dom_core::Players::Two);
-    let mut game_p0 = dom_core::Game::from_mutations(&mutations_for_player(mutations.clone(), dom_core::Player::P0)).unwrap();
-    let mut game_p1 = dom_core::Game::from_mutations(&mutations_for_player(mutations.clone(), dom_core::Player::P1)).unwrap();
+    let mut game_p0 = dom_core::Game::from_mutations(&dom_core::obscure_mutations(mutations.clone(), dom_core::Player::P0)).unwrap();
+    let mut game_p1 = dom_core::Game::from_mutations(&dom_core::obscure_mutations(mutations.clone(), dom_core::Player::P1)).unwrap();
 //    println!("Build initial game\n{:?}\nThen using mutations\n{:?}\nBuilt perspective p0\n{:?}\nAnd perspective p1\n{:?}\n", game, mutations, game_p0, game_p1);
     loop {
-        print_board_state(game.board_state());
+        print!("{}", game.board_state());
         {
-            let perspective = match game.board_state().active_player() {
+            // Show the game as seen by whoever has to make the next move
+            let viewer = game.pending_decision().map_or(game.board_state().active_player(), |d| d.player());
+            let perspective = match viewer {
                 dom_core::Player::P0 => &game_p0,
                 dom_core::Player::P1 => &game_p1,
                 _ => panic!("Game should only have two players"),
             };
             println!("");
-            println!("Game from active player perspective");
+            println!("Game from {:?} perspective", viewer);
             println!("Player 1");
-            show_player(perspective.board_state().get_player(dom_core::Player::P0).unwrap());
+            print!("{}", perspective.board_state().get_player(dom_core::Player::P0).unwrap());
             println!("Player 2");
-            show_player(perspective.board_state().get_player(dom_core::Player::P1).unwrap());
-            println!("Game transition is expecting: {:?}", perspective.state());
+            print!("{}", perspective.board_state().get_player(dom_core::Player::P1).unwrap());
+            // Pending decisions are not part of the mutations, so only the real game knows of them
+            println!("Game transition is expecting: {:?}", game.state());
+            if let Some(decision) = game.pending_decision() {
+                println!("Waiting on {:?}, answer with 'choose' and card names, or 'yes'/'no' to react", decision);
+            }
         }
         println!("");
         println!("");
@@ -157,22 +122,67 @@ fn main() {
         println!("");
         let mut input = String::new();
         io::stdin().read_line(&mut input).unwrap();
-        let maybe_action = match input.trim_right().trim_left() {
+        let maybe_request = match input.trim_right().trim_left() {
             "quit" => return,
-            s => make_action(&game, s),
+            s => make_request(&game, s),
         };
-        if let Some(action) = maybe_action {
-            if let Some(mutations) = game.act(action) {
-                let r0 = game_p0.apply_mutations(&mutations_for_player(mutations.clone(), dom_core::Player::P0));
-                let r1 = game_p1.apply_mutations(&mutations_for_player(mutations.clone(), dom_core::Player::P1));
-                if !r0 || !r1 {
-                    panic!("Failed to apply main game mutations");
-                }
-            } else {
-                println!("Game refused action {:?}", action);
+        if let Some(request) = maybe_request {
+            let outcome = match request {
+                Request::Act(action) => game.act(action).map_err(|err| describe_refusal(action, err)),
+                Request::Resolve(decision, choice) => game.resolve_decision(decision, choice)
+                    .ok_or_else(|| "that doesn't answer the decision".to_string()),
+            };
+            match outcome {
+                Ok(mutations) => {
+                    for (perspective, player) in [(&mut game_p0, dom_core::Player::P0), (&mut game_p1, dom_core::Player::P1)] {
+                        if let Err(err) = perspective.try_apply_mutations(&dom_core::obscure_mutations(mutations.clone(), player)) {
+                            panic!("Failed to apply main game mutations for {:?}: {}", player, err);
+                        }
+                    }
+                    if let Some(result) = game.result() {
+                        println!("Game over: {:?}", result);
+                        return;
+                    }
+                },
+                Err(reason) => println!("Can't do that: {}", reason),
             }
         } else {
             println!("Unknown request");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dom_core::{Action, ActionError, Card, Choice, Decision, Player, State};
+    #[test]
+    fn refusal_messages() {
+        assert_eq!(describe_refusal(Action::Buy(Card::Province), ActionError::NotEnoughGold { cost: 8, gold: 3 }),
+            "not enough gold for Province, it costs 8 and you have 3");
+        assert_eq!(describe_refusal(Action::PlayAction(Card::Smithy), ActionError::WrongState(State::BuyPhase)),
+            "it's not your action phase");
+        assert_eq!(describe_refusal(Action::PlayTreasure(Card::Estate), ActionError::CannotPlay(Card::Estate)),
+            "Estate is not a treasure");
+        assert_eq!(describe_refusal(Action::PlayAction(Card::Village), ActionError::NotInHand(Card::Village)),
+            "you have no Village in hand");
+        assert_eq!(describe_refusal(Action::Buy(Card::Gold), ActionError::PileEmpty(Card::Gold)), "there are no Gold left");
+    }
+    #[test]
+    fn choices_for_decisions() {
+        let cellar = Decision::CellarDiscard { player: Player::P0, max: 4 };
+        assert_eq!(make_choice(cellar, " copper Estate"), Some(Choice::Cards(vec![Card::Copper, Card::Estate])));
+        assert_eq!(make_choice(cellar, ""), Some(Choice::Cards(Vec::new())));
+        assert_eq!(make_choice(cellar, " coppers"), None);
+        let moat = Decision::ReactToAttack { player: Player::P1, attack: Card::Militia };
+        assert_eq!(make_choice(moat, " yes"), Some(Choice::Bool(true)));
+        assert_eq!(make_choice(moat, " moat"), None);
+        let gain = Decision::Gain { player: Player::P0, max_cost: 4 };
+        assert_eq!(make_choice(gain, " smithy"), Some(Choice::Card(Card::Smithy)));
+        assert_eq!(make_choice(gain, ""), Some(Choice::None));
+        assert_eq!(make_choice(gain, " smithy silver"), None);
+        let mine = Decision::MineUpgrade { player: Player::P0 };
+        assert_eq!(make_choice(mine, " copper silver"), Some(Choice::Cards(vec![Card::Copper, Card::Silver])));
+        assert_eq!(make_choice(mine, ""), Some(Choice::None));
+    }
+}